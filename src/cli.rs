@@ -1,34 +1,188 @@
 use std::{
-    fs, iter,
+    env, fs,
+    io::{self, IsTerminal},
     path::{Path, PathBuf},
 };
 
 use clap::Parser;
-use either::Either;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
 
 use crate::Result;
+use crate::config::{Config, NoiseSetting};
+use crate::counting::Counting;
 use crate::error::Error;
+use crate::estimate::Rates;
+use crate::filter::{Noise, TextFilter};
+use crate::fmt::{Column, OutputFormat};
+use crate::goals::Goals;
+use crate::log::{self, plural};
+use crate::matcher::{MatchMode, Matcher};
+use crate::order::{FileOrder, Manifest, Order};
 
 #[derive(Debug, Parser)]
-#[command(author, version, about)]
+#[command(author, version, about, args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[command(flatten)]
     pub common: CommonArgs,
 }
 
-#[derive(Debug, clap::Args)]
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// report words added or removed over time (see `--record`)
+    History(HistoryArgs),
+}
+
+#[derive(Debug, Default, clap::Args)]
 pub struct CommonArgs {
-    /// files, directories, or glob patterns
+    /// files, directories, or glob patterns; `-` for standard input
     paths: Vec<String>,
 
-    /// show only one heading's section (case-insensitive prefix match)
+    /// show only the sections whose headings match (see --match); unless a
+    /// heading matches it whole, `a/b` or `a > b` matches a "b" under an
+    /// "a", and `*` any heading
     #[arg(short, long)]
     filter: Option<String>,
 
+    /// how --filter matches headings [default: prefix]
+    ///
+    /// Every match is shown, with a combined total, and case never matters.
+    #[arg(long = "match", value_enum, value_name = "MODE")]
+    match_mode: Option<MatchMode>,
+
     /// print paragraph count, average, and longest
-    #[arg(short, long)]
+    #[arg(short, long, overrides_with = "no_verbose")]
     verbose: bool,
 
+    #[arg(long, hide = true, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// add these stats columns to the table
+    #[arg(long, value_enum, value_name = "COLUMN", value_delimiter = ',')]
+    columns: Vec<Column>,
+
+    /// show paragraph length percentiles per section instead of totals
+    /// (table only)
+    #[arg(long)]
+    distribution: bool,
+
+    /// list the N longest paragraphs, with their locations (table only)
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+
+    /// how to print the results (ignored in watch mode) [default: table]
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// the order files are counted in [default: name]
+    #[arg(long, value_enum)]
+    order: Option<Order>,
+
+    /// count files in the order this file lists them
+    ///
+    /// An mdBook `SUMMARY.md` (or any Markdown file: its links), a Pandoc
+    /// YAML file (its `input-files`), or a plain list of paths, one per
+    /// line. Files it leaves out follow, in `--order`. With no paths given,
+    /// the listed files are counted.
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// descend into the subdirectories of directory arguments
+    #[arg(short, long, overrides_with = "no_recursive")]
+    recursive: bool,
+
+    #[arg(long, hide = true, overrides_with = "recursive")]
+    no_recursive: bool,
+
+    /// count only the files matching these globs
+    ///
+    /// Like `--exclude`, this only narrows down what directories and glob
+    /// patterns turn up: a file named outright is always counted. A glob
+    /// without a `/` is matched against file names, one with a `/` against
+    /// whole paths.
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// skip the files matching these globs
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// count files with these extensions [default: md,markdown,txt]
+    ///
+    /// Applies to what directories turn up, not to files named outright or
+    /// by a glob pattern. `*` counts files with any extension (or none).
+    #[arg(long = "ext", value_name = "EXT", value_delimiter = ',')]
+    extensions: Vec<String>,
+
+    /// count files even if hidden or listed in `.gitignore` or `.ignore`
+    #[arg(long)]
+    no_ignore: bool,
+
+    /// also skip text matched by these noise rules
+    ///
+    /// Built in: `notes` (`<note ...>`), `comments` (`<!-- -->`) and
+    /// `footnotes`, all on by default; `tk` (`[[TK: ...]]`), `obsidian`
+    /// (`%% ... %%`) and `critic` (`{>> ... <<}`), off by default. More can
+    /// be defined in the `[noise]` table of `ncount.toml`.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    noise: Vec<String>,
+
+    /// count text these noise rules would otherwise skip
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    no_noise: Vec<String>,
+
+    /// what counts as a word [default: unicode]
+    #[arg(long, value_enum, value_name = "RULES")]
+    counting: Option<Counting>,
+
+    /// treat lines indented four spaces after a blank line as code
+    ///
+    /// Fenced code blocks and front matter are never counted; indented
+    /// blocks are counted as prose unless this is set.
+    #[arg(long, overrides_with = "no_indented_code")]
+    indented_code: bool,
+
+    #[arg(long, hide = true, overrides_with = "indented_code")]
+    no_indented_code: bool,
+
+    /// word-count targets to track progress against
+    ///
+    /// A TOML file: `total` (the whole manuscript), `daily` (words written
+    /// today, measured against the `--record` history), and `[levels]` /
+    /// `[headings]` tables mapping a heading level or a heading to a target
+    /// for its section.
+    #[arg(long, value_name = "FILE")]
+    goals: Option<PathBuf>,
+
+    /// target for the whole manuscript (overrides the goals file's `total`)
+    #[arg(long, value_name = "WORDS")]
+    goal: Option<u32>,
+
+    /// reading speed for the `reading` column, in words per minute [default: 238]
+    #[arg(long, value_name = "WPM")]
+    reading_wpm: Option<u32>,
+
+    /// narration speed for the `narration` column, in words per minute [default: 155]
+    #[arg(long, value_name = "WPM")]
+    narration_wpm: Option<u32>,
+
+    /// words per manuscript page for the `pages` column [default: 250]
+    #[arg(long, value_name = "WORDS")]
+    page_words: Option<u32>,
+
+    /// append a snapshot of the totals to the history log
+    ///
+    /// The log lives in `.ncount/history.jsonl`, in the directory that
+    /// holds all of the counted files. `ncount history` reports on it.
+    #[arg(long, overrides_with = "no_record")]
+    record: bool,
+
+    #[arg(long, hide = true, overrides_with = "record")]
+    no_record: bool,
+
     /// watch files and launch the interactive TUI
     ///
     /// Quote glob patterns (`ncount -w 'src/*.md'`) so the shell passes
@@ -36,6 +190,46 @@ pub struct CommonArgs {
     /// and disappear. Press `?` inside the TUI for keybindings.
     #[arg(short, long)]
     watch: bool,
+
+    /// read defaults from this file instead of the nearest `ncount.toml`
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// ignore `ncount.toml`
+    #[arg(long, conflicts_with = "config")]
+    no_config: bool,
+
+    // The rest come only from `ncount.toml` (see `load_config`).
+    #[arg(skip)]
+    config_goals: Option<Goals>,
+
+    #[arg(skip)]
+    config_rates: Rates,
+
+    #[arg(skip)]
+    noise_rules: Noise,
+
+    #[arg(skip)]
+    loaded_manifest: Option<Manifest>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct HistoryArgs {
+    /// the paths the history was recorded for (default: search upward from
+    /// the current directory)
+    paths: Vec<String>,
+
+    /// group changes by day, by week, or by section
+    #[arg(short, long, value_enum, default_value_t)]
+    by: Grouping,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Grouping {
+    #[default]
+    Day,
+    Week,
+    Section,
 }
 
 impl Args {
@@ -44,49 +238,129 @@ impl Args {
     }
 }
 
+impl HistoryArgs {
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    pub fn by(&self) -> Grouping {
+        self.by
+    }
+}
+
 impl CommonArgs {
+    /// Fills in whatever the command line left unset from `ncount.toml`
+    /// (see `apply_config`), then reads the manifest, if there is one.
+    pub fn load_config(&mut self) -> Result<()> {
+        if !self.no_config {
+            let path = match &self.config {
+                Some(path) => Some(path.clone()),
+                None => Config::discover(&env::current_dir()?),
+            };
+            if let Some(path) = path {
+                tracing::debug!("config: {}", path.display());
+                self.apply_config(Config::load(&path)?)?;
+            }
+        }
+
+        if let Some(path) = &self.manifest {
+            let manifest = Manifest::load(path)?;
+            if self.paths.is_empty() {
+                self.paths = manifest
+                    .files()
+                    .iter()
+                    .map(|file| file.to_string_lossy().into_owned())
+                    .collect();
+            }
+            self.loaded_manifest = Some(manifest);
+        }
+
+        // The other formats have nowhere to put the reports; say so rather
+        // than quietly leave them out. (Watch mode ignores all three.)
+        let format = match self.format() {
+            OutputFormat::Table => None,
+            OutputFormat::Json => Some("json"),
+            OutputFormat::Csv => Some("csv"),
+            OutputFormat::Tsv => Some("tsv"),
+        };
+        if let Some(format) = format
+            && !self.watch
+            && (self.distribution || self.top.is_some())
+        {
+            return Err(Error::TableOnly(format));
+        }
+        Ok(())
+    }
+
+    /// Takes defaults from `ncount.toml`: the one named by `--config`, or
+    /// else the nearest one in the current directory or above. Flags given
+    /// on the command line always win, and a switch (`verbose`, `record`)
+    /// turned on there can be turned back off with its `--no-` flag.
+    fn apply_config(&mut self, config: Config) -> Result<()> {
+        if self.paths.is_empty() {
+            self.paths = config.paths;
+        }
+        self.filter = self.filter.take().or(config.filter);
+        self.match_mode = self.match_mode.or(config.match_mode);
+        self.format = self.format.or(config.format);
+        if self.columns.is_empty() {
+            self.columns = config.columns;
+        }
+        self.order = self.order.or(config.order);
+        self.counting = self.counting.or(config.counting);
+        self.manifest = self.manifest.take().or(config.manifest);
+        if self.include.is_empty() {
+            self.include = config.include;
+        }
+        if self.extensions.is_empty() {
+            self.extensions = config.extensions;
+        }
+        self.verbose = switch(self.verbose, self.no_verbose, config.verbose);
+        self.recursive = switch(self.recursive, self.no_recursive, config.recursive);
+        self.indented_code = switch(
+            self.indented_code,
+            self.no_indented_code,
+            config.indented_code,
+        );
+        self.record = switch(self.record, self.no_record, config.record);
+        self.exclude.extend(config.exclude);
+        self.config_goals = config.goals;
+        self.config_rates = config.rates;
+        for (name, setting) in config.noise {
+            match setting {
+                NoiseSetting::Enabled(enabled) => self.noise_rules.set_enabled(&name, enabled)?,
+                NoiseSetting::Pattern(pattern) => self.noise_rules.define(&name, &pattern),
+            }
+        }
+        Ok(())
+    }
+
     pub fn materialize_files(&self) -> Result<Vec<PathBuf>> {
         // Resolve each input (file, dir, or glob) to a list of actual file
         // paths, then canonicalize so the TUI's watch path matches the
         // absolute paths notify hands back. We still have to sort because
         // the default enumeration order on non-Windows file systems is
         // freaking inode order. Thanks, guys!
+        let walk = self.walk()?;
         let mut files = Vec::new();
-        for candidate in &self.paths {
+        let mut skipped = Vec::new();
+        for (rank, candidate) in self.paths.iter().enumerate() {
             let p = Path::new(candidate);
-            let entries: Vec<PathBuf> = if p.exists() {
-                iter_path_files(p).collect()
-            } else {
-                // Absolutize the pattern first: with globwalk's
-                // `max_depth(1)`, a relative pattern containing a directory
-                // component (`src/*.md`) matches nothing at all — the
-                // depth budget is spent before the walk reaches the
-                // matches. (Same fix as `expand_pattern`.)
-                let pattern = match std::path::absolute(p) {
-                    Ok(absolute) => absolute.to_string_lossy().into_owned(),
-                    Err(_) => candidate.clone(),
-                };
-                globwalk::glob_builder(&pattern)
-                    .max_depth(1)
-                    .build()
-                    .into_iter()
-                    .flatten()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.file_type().is_file())
-                    .map(|e| e.into_path())
-                    .collect()
-            };
+            let (entries, passed_over) = walk.expand(candidate);
+            skipped.extend(passed_over);
             if entries.is_empty() {
+                // Say why a directory of the wrong files came up empty.
+                warn_skipped(&walk, &skipped);
                 return Err(Error::FileNotFound(p.to_path_buf()));
             }
-            files.extend(entries);
+            for entry in entries {
+                let file = fs::canonicalize(&entry).map_err(|_| Error::FileNotFound(entry))?;
+                files.push((rank, file));
+            }
         }
-        files.sort();
-        files = files
-            .into_iter()
-            .map(|p| fs::canonicalize(&p).map_err(|_| Error::FileNotFound(p)))
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(files)
+        warn_skipped(&walk, &skipped);
+        self.file_order().sort(&mut files, |(rank, p)| (*rank, p));
+        Ok(files.into_iter().map(|(_, p)| p).collect())
     }
 
     /// Watch-mode counterpart to `materialize_files` (which is run-once's
@@ -98,11 +372,15 @@ impl CommonArgs {
     /// zero files right now.
     pub fn watch_sources(&self) -> Result<Vec<WatchSource>> {
         let mut sources = Vec::new();
+        let mut skipped = Vec::new();
+        let walk = self.walk()?;
         for candidate in &self.paths {
             let p = Path::new(candidate);
             if p.exists() {
-                let entries: Vec<PathBuf> = iter_path_files(p).collect();
+                let (entries, passed_over) = walk.expand(candidate);
+                skipped.extend(passed_over);
                 if entries.is_empty() {
+                    warn_skipped(&walk, &skipped);
                     return Err(Error::FileNotFound(p.to_path_buf()));
                 }
                 let entries = entries
@@ -114,17 +392,127 @@ impl CommonArgs {
                 sources.push(WatchSource::Pattern(candidate.clone()));
             }
         }
+        warn_skipped(&walk, &skipped);
         Ok(sources)
     }
 
+    /// True when the text to count comes from standard input: the only
+    /// path is `-`. Never just because stdin isn't a terminal, which in CI
+    /// or cron can be a pipe that never closes; that only earns a hint.
+    /// There's no project directory to keep a `--record` history in.
+    pub fn reads_stdin(&self) -> Result<bool> {
+        match self.paths.as_slice() {
+            [] => {
+                if !self.watch && !io::stdin().is_terminal() {
+                    log::warn(format_args!(
+                        "no paths given; use `ncount -` to count standard input"
+                    ));
+                }
+                Ok(false)
+            }
+            [path] if path == "-" && !self.watch && !self.record => Ok(true),
+            paths if paths.iter().any(|path| path == "-") => Err(Error::Stdin),
+            _ => Ok(false),
+        }
+    }
+
     pub fn filter(&self) -> Option<&str> {
         self.filter.as_deref()
     }
 
+    pub fn match_mode(&self) -> MatchMode {
+        self.match_mode.unwrap_or_default()
+    }
+
+    /// `--filter`, ready to match headings, if there is one.
+    pub fn heading_matcher(&self) -> Result<Option<Matcher>> {
+        self.filter()
+            .map(|filter| Matcher::new(self.match_mode(), filter))
+            .transpose()
+    }
+
     pub fn verbose(&self) -> bool {
         self.verbose
     }
 
+    pub fn format(&self) -> OutputFormat {
+        self.format.unwrap_or_default()
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn distribution(&self) -> bool {
+        self.distribution
+    }
+
+    pub fn longest(&self) -> Option<usize> {
+        self.top
+    }
+
+    pub fn file_order(&self) -> FileOrder {
+        FileOrder {
+            order: self.order.unwrap_or_default(),
+            manifest: self.loaded_manifest.clone(),
+        }
+    }
+
+    pub fn walk(&self) -> Result<Walk> {
+        let mut walk = Walk::new(&self.include, &self.exclude)?;
+        if !self.extensions.is_empty() {
+            walk.extensions = self
+                .extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect();
+        }
+        walk.recursive = self.recursive;
+        walk.ignore = !self.no_ignore;
+        if let Some(manifest) = &self.loaded_manifest {
+            walk.skip.push(manifest.path().to_path_buf());
+        }
+        Ok(walk)
+    }
+
+    pub fn text_filter(&self) -> Result<TextFilter> {
+        let mut noise = self.noise_rules.clone();
+        for name in &self.noise {
+            noise.set_enabled(name, true)?;
+        }
+        for name in &self.no_noise {
+            noise.set_enabled(name, false)?;
+        }
+        let mut filter = TextFilter::with_noise(&noise)?;
+        filter.set_indented_code(self.indented_code);
+        filter.set_counting(self.counting.unwrap_or_default());
+        Ok(filter)
+    }
+
+    pub fn record(&self) -> bool {
+        self.record
+    }
+
+    pub fn goals(&self) -> Result<Goals> {
+        let mut goals = match &self.goals {
+            Some(path) => Goals::load(path)?,
+            None => self.config_goals.clone().unwrap_or_default(),
+        };
+        if self.goal.is_some() {
+            goals.total = self.goal;
+        }
+        Ok(goals)
+    }
+
+    pub fn rates(&self) -> Rates {
+        let config = self.config_rates;
+        Rates {
+            reading: self.reading_wpm.unwrap_or(config.reading),
+            narration: self.narration_wpm.unwrap_or(config.narration),
+            page: self.page_words.unwrap_or(config.page),
+        }
+    }
+
     pub fn watch(&self) -> bool {
         self.watch
     }
@@ -140,29 +528,205 @@ pub enum WatchSource {
     Pattern(String),
 }
 
+/// Warns about the files directories turned up with the wrong extension.
+fn warn_skipped(walk: &Walk, skipped: &[PathBuf]) {
+    if skipped.is_empty() {
+        return;
+    }
+    log::warn(format_args!(
+        "skipped {} (extension not {}; see --ext): {}",
+        plural(skipped.len(), "file"),
+        walk.extensions.join("/"),
+        skipped
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+}
+
+/// A switch given on the command line (`on`, or `off` for its `--no-`
+/// form) or else in `ncount.toml`.
+fn switch(on: bool, off: bool, config: bool) -> bool {
+    on || (config && !off)
+}
+
+/// How directories and glob patterns turn into files, and which of those
+/// files actually get counted. Files named outright on the command line
+/// always are.
+#[derive(Clone, Debug)]
+pub struct Walk {
+    include: Option<Globs>,
+    exclude: Globs,
+    /// Lowercase, without the dot; `*` for any.
+    pub extensions: Vec<String>,
+    /// Canonical paths of files never to count from directories and
+    /// patterns, like the manifest.
+    pub skip: Vec<PathBuf>,
+    /// Descend into subdirectories of directory arguments (glob patterns
+    /// say for themselves, with `**`).
+    pub recursive: bool,
+    /// Skip hidden files and whatever `.gitignore`/`.ignore` files say to.
+    pub ignore: bool,
+}
+
+const DEFAULT_EXTENSIONS: &[&str] = &["md", "markdown", "txt"];
+
+impl Default for Walk {
+    fn default() -> Self {
+        Self {
+            include: None,
+            exclude: Globs::default(),
+            extensions: DEFAULT_EXTENSIONS.iter().map(|&ext| ext.into()).collect(),
+            skip: Vec::new(),
+            recursive: false,
+            ignore: true,
+        }
+    }
+}
+
+impl Walk {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(Globs::new(include)?)
+        };
+        Ok(Self {
+            include,
+            exclude: Globs::new(exclude)?,
+            ..Default::default()
+        })
+    }
+
+    pub fn accepts(&self, path: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|globs| globs.is_match(path))
+            && !self.exclude.is_match(path)
+            && !self.skips(path)
+    }
+
+    fn skips(&self, path: &Path) -> bool {
+        // Only canonicalize for a likely match.
+        self.skip.iter().any(|skip| {
+            skip.file_name() == path.file_name()
+                && fs::canonicalize(path).is_ok_and(|path| path == *skip)
+        })
+    }
+
+    pub fn has_extension(&self, path: &Path) -> bool {
+        self.extensions.iter().any(|ext| ext == "*")
+            || path.extension().is_some_and(|found| {
+                let found = found.to_string_lossy();
+                self.extensions
+                    .iter()
+                    .any(|ext| found.eq_ignore_ascii_case(ext))
+            })
+    }
+
+    /// The files a command-line candidate stands for: the file itself, the
+    /// files in a directory (or, when recursive, anywhere under it), or the
+    /// files a glob pattern matches. Files a directory turns up with the
+    /// wrong extension come back separately; a pattern already says which
+    /// files it means.
+    fn expand(&self, candidate: &str) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let path = Path::new(candidate);
+        if path.is_file() {
+            (vec![path.to_path_buf()], Vec::new())
+        } else if path.exists() {
+            let depth = if self.recursive { None } else { Some(1) };
+            self.files_under(path, depth)
+                .filter(|path| self.accepts(path))
+                .partition(|path| self.has_extension(path))
+        } else {
+            (self.glob_files(candidate), Vec::new())
+        }
+    }
+
+    /// The files matching a glob pattern. Bad patterns match nothing.
+    fn glob_files(&self, pattern: &str) -> Vec<PathBuf> {
+        // Match against the absolute pattern, since the walk below hands
+        // back paths under its absolute base directory.
+        let pattern = match std::path::absolute(Path::new(pattern)) {
+            Ok(absolute) => absolute.to_string_lossy().into_owned(),
+            Err(_) => pattern.to_string(),
+        };
+        let Ok(glob) = GlobBuilder::new(&pattern).literal_separator(true).build() else {
+            return Vec::new();
+        };
+        let glob = glob.compile_matcher();
+
+        // Only walk as deep as the pattern can reach.
+        self.files_under(&pattern_base_dir(&pattern), pattern_depth(&pattern))
+            .filter(|path| glob.is_match(path) && self.accepts(path))
+            .collect()
+    }
+
+    fn files_under(&self, dir: &Path, depth: Option<usize>) -> impl Iterator<Item = PathBuf> {
+        WalkBuilder::new(dir)
+            .max_depth(depth)
+            .standard_filters(self.ignore)
+            .require_git(false)
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+            .map(|entry| entry.into_path())
+    }
+}
+
+/// Globs matched against file names, or, for those with a directory
+/// component, against whole (absolute) paths.
+#[derive(Clone, Debug, Default)]
+struct Globs {
+    names: GlobSet,
+    paths: GlobSet,
+}
+
+impl Globs {
+    fn new(patterns: &[String]) -> Result<Self> {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = |pattern: &str| {
+                Glob::new(pattern).map_err(|source| Error::Glob {
+                    pattern: pattern.to_string(),
+                    source,
+                })
+            };
+            if pattern.contains('/') {
+                let absolute = std::path::absolute(pattern)?;
+                paths.add(glob(&absolute.to_string_lossy())?);
+            } else {
+                names.add(glob(pattern)?);
+            }
+        }
+        let build = |set: GlobSetBuilder| {
+            set.build().map_err(|source| Error::Glob {
+                pattern: patterns.join(", "),
+                source,
+            })
+        };
+        Ok(Self {
+            names: build(names)?,
+            paths: build(paths)?,
+        })
+    }
+
+    fn is_match(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| self.names.is_match(name))
+            || self.paths.is_match(path)
+    }
+}
+
 /// Expand a glob pattern to sorted, canonicalized file paths, tolerating
-/// zero matches. Mirrors the globwalk invocation inside
-/// `materialize_files` (run-once), which keeps its own copy because
-/// run-once behavior must not change: it errors on zero matches instead.
-pub fn expand_pattern(pattern: &str) -> Vec<PathBuf> {
-    // Absolutize the pattern first: with globwalk's `max_depth(1)`, a
-    // *relative* pattern containing a directory component (`src/*.md`)
-    // matches nothing at all — the depth budget is spent before the walk
-    // reaches the matches. Bare patterns (`*.md`) and absolute patterns
-    // are unaffected. (`materialize_files` had the same bug until it was
-    // ruled a regression and fixed the same way, 2026-07-24.)
-    let pattern = match std::path::absolute(Path::new(pattern)) {
-        Ok(absolute) => absolute.to_string_lossy().into_owned(),
-        Err(_) => pattern.to_string(),
-    };
-    let mut files: Vec<PathBuf> = globwalk::glob_builder(&pattern)
-        .max_depth(1)
-        .build()
+/// zero matches (`materialize_files`, for run-once, errors on zero matches
+/// instead).
+pub fn expand_pattern(pattern: &str, walk: &Walk) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = walk
+        .glob_files(pattern)
         .into_iter()
-        .flatten()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .map(|e| e.into_path())
         .filter_map(|p| fs::canonicalize(&p).ok())
         .collect();
     files.sort();
@@ -170,6 +734,19 @@ pub fn expand_pattern(pattern: &str) -> Vec<PathBuf> {
     files
 }
 
+/// Reads a file to count. Anything but text — not UTF-8, or with NUL bytes
+/// in it — is an `InvalidData` error, which callers skip the file for.
+pub fn read_text(path: &Path) -> io::Result<String> {
+    let text = fs::read_to_string(path)?;
+    if text.contains('\0') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid text",
+        ));
+    }
+    Ok(text)
+}
+
 /// The literal directory prefix of a glob pattern — every component before
 /// the first one containing a metacharacter. That's the directory to watch
 /// for changes in what the pattern matches (needed even when the pattern
@@ -181,7 +758,7 @@ pub fn pattern_base_dir(pattern: &str) -> PathBuf {
         match component {
             std::path::Component::Normal(part) => {
                 let part = part.to_string_lossy();
-                if part.contains(['*', '?', '[']) {
+                if part.contains(['*', '?', '[', '{']) {
                     break;
                 }
                 prefix.push(part.as_ref());
@@ -196,26 +773,28 @@ pub fn pattern_base_dir(pattern: &str) -> PathBuf {
     }
 }
 
-fn iter_path_files(path: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
-    let path = path.as_ref();
-    if path.is_file() {
-        Either::Left(iter::once(path.into()))
-    } else {
-        let paths = fs::read_dir(path)
-            .ok()
-            .into_iter()
-            .flatten()
-            .filter_map(|entry| {
-                let entry = entry.ok()?;
-                entry
-                    .file_type()
-                    .ok()
-                    .map(|kind| (kind, entry))
-                    .filter(|x| x.0.is_file())
-                    .map(|(_, entry)| entry.path())
-            });
-        Either::Right(paths)
+/// How many levels below `pattern_base_dir` a glob pattern can reach: one
+/// for `src/*.md`, three for `book/*/ch-*/scene-*.md`, and no limit at all
+/// for `**` or for alternates with a directory in them (`{a,b/c}`).
+pub fn pattern_depth(pattern: &str) -> Option<usize> {
+    let alternates_directories = pattern
+        .split('{')
+        .skip(1)
+        .any(|rest| rest.split('}').next().is_some_and(|alt| alt.contains('/')));
+    if pattern.contains("**") || alternates_directories {
+        return None;
     }
+    let components = Path::new(pattern).components();
+    let literal = components
+        .clone()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '[', '{'])
+        })
+        .count();
+    Some(components.count() - literal)
 }
 
 #[cfg(test)]
@@ -229,9 +808,7 @@ mod tests {
     fn args(paths: &[&str]) -> CommonArgs {
         CommonArgs {
             paths: paths.iter().map(|s| s.to_string()).collect(),
-            filter: None,
-            verbose: false,
-            watch: false,
+            ..Default::default()
         }
     }
 
@@ -272,6 +849,183 @@ mod tests {
         assert_eq!(files[0], file.canonicalize().unwrap());
     }
 
+    #[test]
+    fn materialize_keeps_listed_order_and_skips_excluded_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        for name in ["a.md", "b.md", "b.md.bak", "z.md"] {
+            std::fs::write(dir.join(name), "# x\n\nhello").unwrap();
+        }
+        let z = dir.join("z.md");
+        let mut args = args(&[z.to_str().unwrap(), dir.to_str().unwrap()]);
+        args.exclude = vec!["*.bak".into(), dir.join("a.*").to_string_lossy().into()];
+
+        assert_eq!(
+            args.materialize_files().unwrap(),
+            [dir.join("b.md"), dir.join("z.md"), dir.join("z.md")]
+        );
+
+        args.order = Some(Order::Listed);
+        assert_eq!(
+            args.materialize_files().unwrap(),
+            [dir.join("z.md"), dir.join("b.md"), dir.join("z.md")]
+        );
+    }
+
+    #[test]
+    fn recursive_walks_respect_include_and_gitignore() {
+        let temp = tempfile::tempdir().unwrap();
+        let book = temp.path().canonicalize().unwrap().join("book");
+        let scenes = book.join("part-1").join("ch-01");
+        std::fs::create_dir_all(&scenes).unwrap();
+        for name in ["scene-1.md", "scene-2.md", "notes.txt", "scratch.md"] {
+            std::fs::write(scenes.join(name), "# x\n\nhello").unwrap();
+        }
+        std::fs::write(book.join(".gitignore"), "scratch.md\n").unwrap();
+        std::fs::write(book.join("intro.md"), "# x\n\nhello").unwrap();
+
+        let mut args = args(&[book.to_str().unwrap()]);
+        args.include = vec!["*.md".into()];
+        assert_eq!(args.materialize_files().unwrap(), [book.join("intro.md")]);
+
+        args.recursive = true;
+        assert_eq!(
+            args.materialize_files().unwrap(),
+            [
+                book.join("intro.md"),
+                scenes.join("scene-1.md"),
+                scenes.join("scene-2.md"),
+            ]
+        );
+
+        args.no_ignore = true;
+        assert_eq!(args.materialize_files().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn expand_pattern_descends_for_double_star_only() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(base.join("a").join("b")).unwrap();
+        std::fs::write(base.join("top.md"), "").unwrap();
+        std::fs::write(base.join("a").join("b").join("deep.md"), "").unwrap();
+
+        let shallow = base.join("*.md").to_string_lossy().into_owned();
+        assert_eq!(
+            expand_pattern(&shallow, &Walk::default()),
+            [base.join("top.md")]
+        );
+
+        let deep = base.join("**").join("*.md").to_string_lossy().into_owned();
+        assert_eq!(
+            expand_pattern(&deep, &Walk::default()),
+            [
+                base.join("a").join("b").join("deep.md"),
+                base.join("top.md")
+            ]
+        );
+    }
+
+    #[test]
+    fn directories_only_yield_text_extensions_unless_told_otherwise() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        for name in ["a.md", "b.png", "c.TXT", "d.docx"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let png = dir.join("b.png");
+
+        let mut args = args(&[dir.to_str().unwrap(), png.to_str().unwrap()]);
+        // Named outright, the image is counted anyway.
+        assert_eq!(
+            args.materialize_files().unwrap(),
+            [dir.join("a.md"), dir.join("b.png"), dir.join("c.TXT")]
+        );
+
+        args.paths.pop();
+        args.extensions = vec![".docx".into()];
+        assert_eq!(args.materialize_files().unwrap(), [dir.join("d.docx")]);
+
+        args.extensions = vec!["*".into()];
+        assert_eq!(args.materialize_files().unwrap().len(), 4);
+
+        // A pattern says for itself which files it means.
+        args.extensions = Vec::new();
+        args.paths = vec![dir.join("*.docx").to_string_lossy().into_owned()];
+        assert_eq!(args.materialize_files().unwrap(), [dir.join("d.docx")]);
+    }
+
+    #[test]
+    fn read_text_rejects_binary_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let utf16 = temp.path().join("utf16.md");
+        std::fs::write(&utf16, "#\0 \0x\0").unwrap();
+        let latin1 = temp.path().join("latin1.md");
+        std::fs::write(&latin1, b"caf\xe9").unwrap();
+
+        for path in [utf16, latin1] {
+            let err = read_text(&path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn config_switches_can_be_turned_off_from_the_command_line() {
+        let config = || Config {
+            verbose: true,
+            record: true,
+            ..Default::default()
+        };
+        let parse = |flags: &[&str]| {
+            let mut args = Args::try_parse_from([&["ncount"], flags].concat())
+                .unwrap()
+                .common;
+            args.apply_config(config()).unwrap();
+            (args.verbose(), args.record())
+        };
+        assert_eq!(parse(&[]), (true, true));
+        assert_eq!(parse(&["--no-verbose"]), (false, true));
+        assert_eq!(parse(&["--no-record", "-v"]), (true, false));
+        assert_eq!(parse(&["-v", "--no-verbose"]), (false, true));
+    }
+
+    #[test]
+    fn reports_only_go_with_the_table() {
+        let load = |flags: &[&str]| {
+            let mut args = Args::try_parse_from([&["ncount", "--no-config"], flags].concat())
+                .unwrap()
+                .common;
+            args.load_config()
+        };
+        assert!(load(&["--distribution", "--top", "3"]).is_ok());
+        assert!(matches!(
+            load(&["--format", "json", "--top", "3"]),
+            Err(Error::TableOnly("json"))
+        ));
+        assert!(matches!(
+            load(&["--distribution", "--format", "csv"]),
+            Err(Error::TableOnly("csv"))
+        ));
+    }
+
+    #[test]
+    fn dash_reads_stdin_but_only_on_its_own() {
+        assert!(args(&["-"]).reads_stdin().unwrap());
+        assert!(!args(&["a.md"]).reads_stdin().unwrap());
+        assert!(matches!(
+            args(&["-", "a.md"]).reads_stdin(),
+            Err(Error::Stdin)
+        ));
+
+        let mut watching = args(&["-"]);
+        watching.watch = true;
+        assert!(matches!(watching.reads_stdin(), Err(Error::Stdin)));
+
+        let mut recording = args(&["-"]);
+        recording.record = true;
+        assert!(matches!(recording.reads_stdin(), Err(Error::Stdin)));
+    }
+
     #[test]
     fn materialize_reports_missing_file_with_path() {
         let err = args(&["definitely/does/not/exist.md"])
@@ -312,7 +1066,7 @@ mod tests {
 
         assert!(matches!(&sources[0], WatchSource::Pattern(_)));
         // ...and expanding it right now simply yields nothing.
-        assert!(expand_pattern(&pattern).is_empty());
+        assert!(expand_pattern(&pattern, &Walk::default()).is_empty());
     }
 
     #[test]
@@ -338,7 +1092,7 @@ mod tests {
         std::fs::write(temp.path().join("not-md.txt"), "x").unwrap();
 
         let pattern = temp.path().join("*.md").to_string_lossy().into_owned();
-        let files = expand_pattern(&pattern);
+        let files = expand_pattern(&pattern, &Walk::default());
 
         assert_eq!(
             files,
@@ -393,7 +1147,7 @@ mod tests {
         std::fs::write(&file, "x").unwrap();
 
         let _guard = CurrentDirGuard::enter(temp.path());
-        let files = expand_pattern("src/chapter.*");
+        let files = expand_pattern("src/chapter.*", &Walk::default());
 
         assert_eq!(files, vec![file.canonicalize().unwrap()]);
     }
@@ -407,5 +1161,38 @@ mod tests {
             PathBuf::from("/books/src")
         );
         assert_eq!(pattern_base_dir("a/b?c/d.md"), PathBuf::from("a"));
+        assert_eq!(pattern_base_dir("book/{a,b}.md"), PathBuf::from("book"));
+    }
+
+    #[test]
+    fn pattern_depth_counts_the_wildcard_levels() {
+        assert_eq!(pattern_depth("*.md"), Some(1));
+        assert_eq!(pattern_depth("/books/src/*.md"), Some(1));
+        assert_eq!(pattern_depth("book/*/ch-*/scene-*.md"), Some(3));
+        assert_eq!(pattern_depth("book/{a,b}.md"), Some(1));
+        assert_eq!(pattern_depth("book/{a,b/c}.md"), None);
+        assert_eq!(pattern_depth("book/**/*.md"), None);
+    }
+
+    #[test]
+    fn expand_pattern_expands_alternates() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(base.join("book").join("c")).unwrap();
+        for name in ["a.md", "b.md", "c/d.md", "e.md"] {
+            std::fs::write(base.join("book").join(name), "").unwrap();
+        }
+
+        let pattern = base.join("book/{a,b}.md").to_string_lossy().into_owned();
+        assert_eq!(
+            expand_pattern(&pattern, &Walk::default()),
+            [base.join("book/a.md"), base.join("book/b.md")]
+        );
+
+        let pattern = base.join("book/{a,c/d}.md").to_string_lossy().into_owned();
+        assert_eq!(
+            expand_pattern(&pattern, &Walk::default()),
+            [base.join("book/a.md"), base.join("book/c/d.md")]
+        );
     }
 }
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::Result;
+use crate::counting::Counting;
+use crate::error::Error;
+use crate::estimate::Rates;
+use crate::fmt::{Column, OutputFormat};
+use crate::goals::Goals;
+use crate::matcher::MatchMode;
+use crate::order::Order;
+
+pub const FILE_NAME: &str = "ncount.toml";
+
+/// Project defaults, read from the nearest `ncount.toml`. Every key is
+/// optional and every one has a command-line counterpart that overrides it:
+///
+/// ```toml
+/// paths = ["src/*.md"]         # what to count when no paths are given
+/// order = "listed"             # --order
+/// manifest = "src/SUMMARY.md"  # --manifest
+/// recursive = true             # --recursive
+/// include = ["*.md"]           # --include
+/// extensions = ["md", "txt"]   # --ext
+/// exclude = ["drafts/*"]       # --exclude
+/// filter = "chapter iii"       # --filter
+/// match = "substring"          # --match
+/// format = "table"             # --format
+/// verbose = true               # --verbose
+/// columns = ["sentences"]      # --columns
+/// record = true                # --record
+/// counting = "word"            # --counting
+/// indented_code = true         # --indented-code
+///
+/// [goals]                      # --goals, same keys as a goals file
+/// total = 90000
+///
+/// [rates]                      # --reading-wpm, --narration-wpm, --page-words
+/// page = 300
+///
+/// [noise]                      # --noise / --no-noise
+/// tk = true                    # turn a rule on (or off)...
+/// todo = 'TODO\(.*?\)'         # ...or define a new one
+/// ```
+///
+/// Relative paths (`paths`, and `include`/`exclude` patterns with a
+/// directory component) are relative to the file itself, not the current directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub paths: Vec<String>,
+    pub order: Option<Order>,
+    pub manifest: Option<PathBuf>,
+    #[serde(default)]
+    pub recursive: bool,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    pub filter: Option<String>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<MatchMode>,
+    pub format: Option<OutputFormat>,
+    #[serde(default)]
+    pub verbose: bool,
+    #[serde(default)]
+    pub columns: Vec<Column>,
+    #[serde(default)]
+    pub record: bool,
+    pub counting: Option<Counting>,
+    #[serde(default)]
+    pub indented_code: bool,
+    pub goals: Option<Goals>,
+    #[serde(default)]
+    pub rates: Rates,
+    #[serde(default)]
+    pub noise: BTreeMap<String, NoiseSetting>,
+}
+
+/// One entry of the `[noise]` table.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NoiseSetting {
+    /// Turns the named rule on or off.
+    Enabled(bool),
+    /// Defines a rule (replacing a built-in one of the same name).
+    Pattern(String),
+}
+
+impl Config {
+    /// The `ncount.toml` in `start` or the nearest of its ancestors.
+    pub fn discover(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&text).map_err(|source| Error::Config {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        if let Some(manifest) = &mut config.manifest {
+            *manifest = dir.join(&*manifest);
+        }
+        for candidate in &mut config.paths {
+            *candidate = rebase(&dir, candidate);
+        }
+        for pattern in config.include.iter_mut().chain(&mut config.exclude) {
+            if pattern.contains('/') {
+                *pattern = rebase(&dir, pattern);
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn rebase(dir: &Path, path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        dir.join(path).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discover_searches_upward() {
+        let temp = tempfile::tempdir().unwrap();
+        let nested = temp.path().join("book").join("src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(temp.path().join("book").join(FILE_NAME), "").unwrap();
+
+        assert_eq!(
+            Config::discover(&nested),
+            Some(temp.path().join("book").join(FILE_NAME))
+        );
+        assert_eq!(Config::discover(temp.path()), None);
+    }
+
+    #[test]
+    fn load_rebases_relative_paths_onto_the_config_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        let path = dir.join(FILE_NAME);
+        fs::write(
+            &path,
+            "paths = ['src/*.md', '/abs/b.md']\nexclude = ['*.bak', 'drafts/*']\n\
+             format = 'csv'\norder = 'listed'\n[goals]\ntotal = 100\n\
+             [noise]\ntk = true\ntodo = 'TODO'\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(
+            config.paths,
+            [dir.join("src/*.md").to_string_lossy(), "/abs/b.md".into()]
+        );
+        assert_eq!(
+            config.exclude,
+            ["*.bak".into(), dir.join("drafts/*").to_string_lossy()]
+        );
+        assert_eq!(config.format, Some(OutputFormat::Csv));
+        assert_eq!(config.order, Some(Order::Listed));
+        assert_eq!(config.goals.unwrap().total, Some(100));
+        assert!(matches!(config.noise["tk"], NoiseSetting::Enabled(true)));
+        assert!(matches!(&config.noise["todo"], NoiseSetting::Pattern(p) if p == "TODO"));
+    }
+
+    #[test]
+    fn unknown_keys_are_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(FILE_NAME);
+        fs::write(&path, "verbsoe = true\n").unwrap();
+        assert!(matches!(Config::load(&path), Err(Error::Config { .. })));
+    }
+}
@@ -0,0 +1,229 @@
+use serde::Deserialize;
+
+use crate::document::{ParagraphStats, count_cjk_words, count_words, is_cjk};
+
+/// Quotation marks: `"` opens and closes, the others only one or the other.
+const QUOTES: [char; 9] = ['"', '“', '”', '«', '»', '「', '」', '『', '』'];
+
+/// What counts as a word. Different tools (and publishers) disagree, mostly
+/// about hyphens, dashes, and stray punctuation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Counting {
+    /// Unicode word boundaries: `co-authored` is two words, `—` is none
+    #[default]
+    Unicode,
+    /// anything between spaces, like `wc -w`: `—` on its own is a word
+    Whitespace,
+    /// like Microsoft Word: `co-authored` and `yes—no` are one word each,
+    /// and punctuation on its own is none
+    Word,
+    /// characters, spaces included, divided by six
+    Characters,
+    /// Chinese and Japanese characters one by one, and other scripts'
+    /// words as `unicode` does
+    Cjk,
+}
+
+/// A line's measurements so far, taken piece by piece as it's assembled.
+#[derive(Debug, Default)]
+pub struct Tally {
+    words: u32,
+    chars: u32,
+    chars_no_spaces: u32,
+    sentences: u32,
+    dialogue: u32,
+    cjk_chars: u32,
+    /// Whether the text so far has left a quotation open.
+    quoted: bool,
+}
+
+impl Counting {
+    /// Adds `text`, the next piece of a line, to `tally`.
+    pub fn tally(self, tally: &mut Tally, text: &str) {
+        tally.words += self.measure(text);
+        if text.is_ascii() {
+            tally.chars += text.len() as u32;
+            tally.chars_no_spaces +=
+                text.bytes().filter(|b| !b.is_ascii_whitespace()).count() as u32;
+        } else {
+            tally.chars += text.chars().count() as u32;
+            tally.chars_no_spaces += text.chars().filter(|c| !c.is_whitespace()).count() as u32;
+            tally.cjk_chars += text.chars().filter(|&c| is_cjk(c)).count() as u32;
+        }
+        tally.sentences += sentence_ends(text);
+
+        let mut rest = text;
+        loop {
+            let end = rest.find(QUOTES).unwrap_or(rest.len());
+            if tally.quoted {
+                tally.dialogue += self.measure(&rest[..end]);
+            }
+            let Some(mark) = rest[end..].chars().next() else {
+                break;
+            };
+            tally.quoted = match mark {
+                '"' => !tally.quoted,
+                '“' | '«' | '「' | '『' => true,
+                _ => false,
+            };
+            rest = &rest[end + mark.len_utf8()..];
+        }
+    }
+
+    /// A finished line's stats. Text that doesn't end in a full stop is
+    /// still a sentence.
+    pub fn finish(self, tally: Tally) -> ParagraphStats {
+        let words = self.words(tally.words);
+        let sentences = if words > 0 {
+            tally.sentences.max(1)
+        } else {
+            tally.sentences
+        };
+        ParagraphStats {
+            words,
+            chars: tally.chars,
+            chars_no_spaces: tally.chars_no_spaces,
+            sentences,
+            dialogue_words: self.words(tally.dialogue).min(words),
+            cjk_chars: tally.cjk_chars,
+            // Filled in by the lexer.
+            location: Default::default(),
+            by_characters: self == Counting::Characters,
+        }
+    }
+
+    /// What `text` adds to its line's word tally: words, or for
+    /// `Characters`, characters. Pure-ASCII text skips the Unicode
+    /// machinery where the rules allow it.
+    fn measure(self, text: &str) -> u32 {
+        match self {
+            Counting::Unicode => count_words(text),
+            Counting::Whitespace if text.is_ascii() => text.split_ascii_whitespace().count() as u32,
+            Counting::Whitespace => text.split_whitespace().count() as u32,
+            Counting::Word if text.is_ascii() => text
+                .split_ascii_whitespace()
+                .filter(|token| token.bytes().any(|b| b.is_ascii_alphanumeric()))
+                .count() as u32,
+            Counting::Word => text
+                .split_whitespace()
+                .filter(|token| token.chars().any(char::is_alphanumeric))
+                .count() as u32,
+            Counting::Characters if text.is_ascii() => text.len() as u32,
+            Counting::Characters => text.chars().count() as u32,
+            Counting::Cjk => count_cjk_words(text),
+        }
+    }
+
+    /// Words, given a tally: for `Characters`, characters over six, rounded.
+    /// That's done once a whole line is in, and again over the characters
+    /// of a whole section (see `Paragraphs`), so the rounding doesn't pile
+    /// up.
+    pub fn words(self, tally: u32) -> u32 {
+        match self {
+            Counting::Characters => (tally + 3) / 6,
+            _ => tally,
+        }
+    }
+}
+
+/// Sentence-ending punctuation (`.`, `!`, `?`, `…`, or a run of them) right
+/// after a word and followed by a space, a closing mark, or the end. So
+/// `3.14` and `...and` don't end sentences, but `Mr.` does. Full-width stops
+/// (`。`, `！`, `？`) need no space after them.
+fn sentence_ends(text: &str) -> u32 {
+    let is_stop = |c: char| matches!(c, '.' | '!' | '?' | '…' | '。' | '！' | '？');
+    let is_full_width = |c: char| matches!(c, '。' | '！' | '？');
+    let closes = |c: char| matches!(c, '"' | '”' | '’' | '\'' | ')' | '»' | '」' | '』');
+    let mut count = 0;
+    let mut after_word = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_stop(c) {
+            let mut full_width = is_full_width(c);
+            while let Some(c) = chars.next_if(|&c| is_stop(c)) {
+                full_width |= is_full_width(c);
+            }
+            let at_end = full_width
+                || chars
+                    .peek()
+                    .is_none_or(|&next| next.is_whitespace() || closes(next));
+            if after_word && at_end {
+                count += 1;
+            }
+            after_word = false;
+        } else {
+            after_word = c.is_alphanumeric() || closes(c);
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(counting: Counting, text: &str) -> u32 {
+        counting.words(counting.measure(text))
+    }
+
+    #[test]
+    fn presets_disagree_about_dashes_and_punctuation() {
+        let text = "A well-known co-authored draft — yes—no? * Ça va.";
+        assert_eq!(count(Counting::Unicode, text), 10);
+        assert_eq!(count(Counting::Whitespace, text), 9);
+        assert_eq!(count(Counting::Word, text), 7);
+        assert_eq!(count(Counting::Characters, text), 8);
+    }
+
+    fn stats_with(counting: Counting, text: &str) -> ParagraphStats {
+        let mut tally = Tally::default();
+        counting.tally(&mut tally, text);
+        counting.finish(tally)
+    }
+
+    fn stats(text: &str) -> ParagraphStats {
+        stats_with(Counting::Unicode, text)
+    }
+
+    #[test]
+    fn characters_sentences_and_dialogue() {
+        let p = stats("\"Wait... it's 3.14?\" she asked. “Yes,” he said, “it is”");
+        assert_eq!(p.words, 10);
+        assert_eq!(p.chars, 55);
+        assert_eq!(p.chars_no_spaces, 46);
+        assert_eq!(p.sentences, 3);
+        assert_eq!(p.dialogue_words, 6);
+
+        assert_eq!(stats("no stop at all").sentences, 1);
+        assert_eq!(stats("").sentences, 0);
+    }
+
+    #[test]
+    fn quotations_carry_across_pieces() {
+        let mut tally = Tally::default();
+        Counting::Unicode.tally(&mut tally, "\"One two");
+        Counting::Unicode.tally(&mut tally, " three\" four");
+        assert_eq!(Counting::Unicode.finish(tally).dialogue_words, 3);
+    }
+
+    #[test]
+    fn cjk_counts_characters_and_latin_words() {
+        let text = "「東京へ行く。」と彼は言った。Tokyo is far.";
+        let p = stats_with(Counting::Cjk, text);
+        assert_eq!(p.words, 14);
+        assert_eq!(p.cjk_chars, 11);
+        assert_eq!(p.sentences, 3);
+        assert_eq!(p.dialogue_words, 5);
+
+        assert_eq!(count(Counting::Cjk, "カタカナ and 日本語"), 8);
+        assert_eq!(count(Counting::Cjk, "plain ascii"), 2);
+    }
+
+    #[test]
+    fn characters_round_to_the_nearest_word() {
+        assert_eq!(count(Counting::Characters, "Yes."), 1);
+        assert_eq!(count(Counting::Characters, "No"), 0);
+        assert_eq!(count(Counting::Characters, "twelve chars"), 2);
+    }
+}
@@ -1,14 +1,22 @@
-use std::{iter, ops};
+use std::{iter, ops, path::Path, sync::Arc};
 
 use compact_str::CompactString;
+use serde::Serialize;
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::counting::Counting;
 use crate::filter::LineEvent;
+use crate::matcher::Matcher;
+
+/// Joins a section's heading onto its ancestors' wherever a section is
+/// named by its whole path (`Chapter I > 1.1`) rather than its heading.
+pub const PATH_SEPARATOR: &str = " > ";
 
 #[derive(Clone, Debug)]
 pub struct DocumentBuilder {
     root: Document,
     current_level: i32,
+    source: Option<Arc<Path>>,
 }
 
 impl DocumentBuilder {
@@ -16,22 +24,31 @@ impl DocumentBuilder {
         Self {
             root: Document::new(0),
             current_level: 0,
+            source: None,
         }
     }
 
+    /// Names the file the next `apply` call's events come from. Every
+    /// section started from here on records it as its source; sections
+    /// started before keep theirs.
+    pub fn set_source(&mut self, path: &Path) {
+        self.source = Some(Arc::from(path));
+    }
+
     pub fn finalize(self) -> Document {
         self.root
     }
 
     /// Applies an already-lexed stream of heading/paragraph events (see
     /// `filter::TextFilter::lex`) to the tree. A heading updates the current
-    /// level and starts a new document; a paragraph adds its word count to
-    /// whichever document is current.
+    /// level and starts a new document; a paragraph adds its word count (and
+    /// a line of code, its line) to whichever document is current.
     pub fn apply(&mut self, events: impl Iterator<Item = LineEvent>) {
+        let source = self.source.clone();
         let mut target = self.root.current_document(self.current_level);
         for event in events {
             match event {
-                LineEvent::Heading(heading, level) => {
+                LineEvent::Heading(heading, level, location) => {
                     tracing::debug!(
                         heading = heading.as_str(),
                         level,
@@ -40,11 +57,14 @@ impl DocumentBuilder {
                     );
                     target = self.root.new_document(level);
                     target.set_heading(heading);
+                    target.source = source.clone();
+                    target.location = location;
                     self.current_level = level;
                 }
-                LineEvent::Paragraph(word_count) => {
-                    target.add_paragraph(word_count);
+                LineEvent::Paragraph(stats) => {
+                    target.add_paragraph(stats, source.clone());
                 }
+                LineEvent::Code => target.code_lines += 1,
             }
         }
     }
@@ -62,6 +82,38 @@ pub(crate) fn count_words(s: &str) -> u32 {
     }
 }
 
+/// Word count for Chinese and Japanese, which are measured by character:
+/// every CJK character (see `is_cjk`) counts as one, and the text between
+/// them is counted as usual, so mixed-script text gets a meaningful total.
+pub(crate) fn count_cjk_words(s: &str) -> u32 {
+    if s.is_ascii() {
+        return ascii_word_count(s);
+    }
+    let mut count = 0;
+    let mut rest = s;
+    while let Some(i) = rest.find(is_cjk) {
+        count += count_words(&rest[..i]) + 1;
+        let c = rest[i..].chars().next().unwrap_or_default();
+        rest = &rest[i + c.len_utf8()..];
+    }
+    count + count_words(rest)
+}
+
+/// Han ideographs, kana, and the kana length mark, but not CJK punctuation.
+/// Hangul isn't included: Korean puts spaces between its words.
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{309F}' // Hiragana
+        | '\u{30A0}'..='\u{30FA}' | '\u{30FC}'..='\u{30FF}' // Katakana, without `・`
+        | '\u{31F0}'..='\u{31FF}' // Katakana phonetic extensions
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9D}' // Halfwidth katakana
+        | '\u{20000}'..='\u{3134F}' // CJK Extensions B–G
+    )
+}
+
 /// ASCII word count matched to `unicode_words()` by empirical probing: only
 /// `' . : , ;` ever join two word runs, and only a single occurrence
 /// between the right adjacent character classes (letters for `'` `.` `:`,
@@ -125,7 +177,15 @@ fn ascii_word_count(s: &str) -> u32 {
 pub struct Document {
     heading: Option<CompactString>,
     level: i32,
+    /// The file this section's heading was read from, when known.
+    source: Option<Arc<Path>>,
+    /// Where in that file the heading is.
+    location: Location,
     paragraphs: Paragraphs,
+    /// Each of those paragraphs, in order.
+    records: Vec<ParagraphRecord>,
+    /// Non-blank lines of code, which count for nothing in `paragraphs`.
+    code_lines: u32,
     subdocuments: Vec<Document>,
 }
 
@@ -134,24 +194,54 @@ impl Document {
         Self {
             heading: None,
             level,
+            source: None,
+            location: Location::default(),
             paragraphs: Paragraphs::new(),
+            records: Vec::new(),
+            code_lines: 0,
             subdocuments: Vec::new(),
         }
     }
 
-    pub fn get_heading(&self, heading: &str) -> Option<&Document> {
-        let document = self.subdocuments.iter().find(|&x| {
-            x.heading
-                .as_ref()
-                .map(|x| x.to_ascii_uppercase().starts_with(heading))
-                .unwrap_or_default()
-        });
+    /// Every section `matcher` matches (by its heading, and those of its
+    /// ancestors), in document order. A match brings its subsections along,
+    /// so they aren't matched again.
+    pub fn find_headings(&self, matcher: &Matcher) -> Vec<&Document> {
+        let mut found = Vec::new();
+        self.collect_headings(matcher, &mut Vec::new(), &mut found);
+        found
+    }
+
+    fn collect_headings<'a>(
+        &'a self,
+        matcher: &Matcher,
+        path: &mut Vec<&'a str>,
+        found: &mut Vec<&'a Document>,
+    ) {
+        if let Some(heading) = self.heading.as_deref() {
+            path.push(heading);
+            if matcher.is_match(path) {
+                found.push(self);
+                path.pop();
+                return;
+            }
+        }
+        for document in &self.subdocuments {
+            document.collect_headings(matcher, path, found);
+        }
+        if self.heading.is_some() {
+            path.pop();
+        }
+    }
 
-        let mut fallback = self
-            .subdocuments
-            .iter()
-            .filter_map(|x| x.get_heading(heading));
-        document.or_else(|| fallback.next())
+    /// A document of copies of `sections` side by side (under no heading of
+    /// its own), so a filter's matches can be reported, and totalled, like
+    /// any other document.
+    pub fn combine(sections: &[&Document]) -> Document {
+        Document {
+            subdocuments: sections.iter().map(|&x| x.clone()).collect(),
+            ..Document::new(0)
+        }
     }
 }
 
@@ -191,8 +281,13 @@ impl Document {
         }
     }
 
-    fn add_paragraph(&mut self, p: u32) {
+    fn add_paragraph(&mut self, p: ParagraphStats, source: Option<Arc<Path>>) {
         self.paragraphs.add(p);
+        self.records.push(ParagraphRecord {
+            words: p.words,
+            location: p.location,
+            source,
+        });
     }
 
     fn set_heading(&mut self, heading: CompactString) {
@@ -209,10 +304,11 @@ impl Document {
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct DocumentStats<'a>(&'a Document);
 
-impl DocumentStats<'_> {
-    pub fn heading(&self) -> Option<&str> {
+impl<'a> DocumentStats<'a> {
+    pub fn heading(&self) -> Option<&'a str> {
         self.0.heading.as_deref()
     }
 
@@ -220,11 +316,29 @@ impl DocumentStats<'_> {
         self.0.level
     }
 
+    pub fn source(&self) -> Option<&'a Path> {
+        self.0.source.as_deref()
+    }
+
+    /// Where the section's heading is in `source`.
+    pub fn location(&self) -> Location {
+        self.0.location
+    }
+
     pub fn paragraphs(&self) -> Paragraphs {
         self.0.paragraphs
     }
 
-    pub fn children(&self) -> impl Iterator<Item = DocumentStats<'_>> + '_ {
+    pub fn code_lines(&self) -> u32 {
+        self.0.code_lines
+    }
+
+    /// The section's own paragraphs, one by one.
+    pub fn records(&self) -> &'a [ParagraphRecord] {
+        &self.0.records
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = DocumentStats<'a>> + 'a {
         self.0.subdocuments.iter().map(DocumentStats)
     }
 
@@ -241,8 +355,43 @@ impl DocumentStats<'_> {
     }
 }
 
-/// A summary of the paragraphs of a document section
+/// Where a heading or paragraph starts in the text it was lexed from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Location {
+    /// counting from 1, and counting lines of removed noise
+    pub line: u32,
+    /// bytes from the start of the text to its first counted character
+    pub offset: usize,
+}
+
+/// Where a paragraph is, and how long.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParagraphRecord {
+    pub words: u32,
+    pub location: Location,
+    /// The file it was read from, when known.
+    pub source: Option<Arc<Path>>,
+}
+
+/// What the lexer measures of a single paragraph.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParagraphStats {
+    pub words: u32,
+    /// characters, spaces included
+    pub chars: u32,
+    pub chars_no_spaces: u32,
+    pub sentences: u32,
+    /// words inside quotation marks
+    pub dialogue_words: u32,
+    /// Chinese and Japanese characters
+    pub cjk_chars: u32,
+    pub location: Location,
+    /// Whether `words` is `chars` over six (`--counting characters`).
+    pub by_characters: bool,
+}
+
+/// A summary of the paragraphs of a document section
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
 pub struct Paragraphs {
     /// count of the paragraphs in the section
     pub count: u32,
@@ -250,6 +399,21 @@ pub struct Paragraphs {
     pub max: u32,
     /// total length of all paragraphs
     pub total: u32,
+    /// characters in all paragraphs, spaces included
+    pub chars: u32,
+    /// characters in all paragraphs, not counting whitespace
+    pub chars_no_spaces: u32,
+    /// sentences in all paragraphs
+    pub sentences: u32,
+    /// words inside quotation marks
+    pub dialogue_words: u32,
+    /// Chinese and Japanese characters in all paragraphs
+    pub cjk_chars: u32,
+    /// Whether words are characters over six, in which case `total` is
+    /// worked out from `chars` rather than added up paragraph by paragraph,
+    /// so rounding each short paragraph doesn't throw it off.
+    #[serde(skip)]
+    pub by_characters: bool,
 }
 
 impl Paragraphs {
@@ -257,18 +421,38 @@ impl Paragraphs {
         Default::default()
     }
 
-    fn add(&mut self, p: u32) {
+    fn add(&mut self, p: ParagraphStats) {
         self.count += 1;
-        self.max = self.max.max(p);
-        self.total += p;
+        self.max = self.max.max(p.words);
+        self.total += p.words;
+        self.chars += p.chars;
+        self.chars_no_spaces += p.chars_no_spaces;
+        self.sentences += p.sentences;
+        self.dialogue_words += p.dialogue_words;
+        self.cjk_chars += p.cjk_chars;
+        self.by_characters |= p.by_characters;
+        self.divide_characters();
     }
 
     /// Fold another section's paragraph summary into this one, combining
-    /// count/max/total the way `add` would over the same paragraphs.
+    /// every field the way `add` would over the same paragraphs.
     pub(crate) fn merge(&mut self, other: Paragraphs) {
         self.count += other.count;
         self.max = self.max.max(other.max);
         self.total += other.total;
+        self.chars += other.chars;
+        self.chars_no_spaces += other.chars_no_spaces;
+        self.sentences += other.sentences;
+        self.dialogue_words += other.dialogue_words;
+        self.cjk_chars += other.cjk_chars;
+        self.by_characters |= other.by_characters;
+        self.divide_characters();
+    }
+
+    fn divide_characters(&mut self) {
+        if self.by_characters {
+            self.total = Counting::Characters.words(self.chars);
+        }
     }
 
     pub fn is_zero(&self) -> bool {
@@ -278,30 +462,36 @@ impl Paragraphs {
     pub fn average_len(&self) -> u32 {
         (self.total as f64 / self.count as f64).round() as u32
     }
+
+    /// Words per sentence, or 0 with no sentences at all.
+    pub fn average_sentence_len(&self) -> u32 {
+        if self.sentences == 0 {
+            return 0;
+        }
+        (self.total as f64 / self.sentences as f64).round() as u32
+    }
+
+    /// The share of words that are dialogue, as a whole percentage.
+    pub fn dialogue_percent(&self) -> u32 {
+        if self.total == 0 {
+            return 0;
+        }
+        (self.dialogue_words as f64 * 100.0 / self.total as f64).round() as u32
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct OverallStats {
-    /// count of all paragraphs
-    pub count: u32,
-    /// length of the longest paragraph
-    pub max: u32,
-    /// total length of all paragraphs
-    pub total: u32,
-}
-
-impl OverallStats {
-    pub fn average_len(&self) -> u32 {
-        (self.total as f64 / self.count as f64).round() as u32
-    }
+    /// every section's paragraphs, merged
+    pub paragraphs: Paragraphs,
+    /// lines of code
+    pub code_lines: u32,
 }
 
 impl<'a> ops::AddAssign<DocumentStats<'a>> for OverallStats {
     fn add_assign(&mut self, rhs: DocumentStats<'a>) {
-        let p = rhs.paragraphs();
-        self.count += p.count;
-        self.max = self.max.max(p.max);
-        self.total += p.total;
+        self.paragraphs.merge(rhs.paragraphs());
+        self.code_lines += rhs.code_lines();
     }
 }
 
@@ -326,6 +516,22 @@ mod tests {
         builder.finalize()
     }
 
+    #[test]
+    fn characters_are_divided_once_per_section() {
+        let mut filter = crate::filter::TextFilter::new();
+        filter.set_counting(Counting::Characters);
+        let mut builder = DocumentBuilder::new();
+        builder.apply(filter.lex(&format!("# One\n\n{}", "No.\n\n".repeat(1000))));
+        builder.apply(filter.lex("# Two\n\nNo.\n\nNo.\n"));
+        let doc = builder.finalize();
+
+        let sections: Vec<_> = doc.iter().map(|stats| stats.paragraphs()).collect();
+        assert_eq!(sections[0].total, 500);
+        assert_eq!(sections[0].max, 1);
+        assert_eq!(sections[1].total, 1);
+        assert_eq!(doc.iter().collect::<OverallStats>().paragraphs.total, 501);
+    }
+
     #[test]
     fn single_heading_single_paragraph_counts_words() {
         let doc = build("# Chapter\n\nSome words here.");
@@ -370,14 +576,8 @@ mod tests {
         );
         let stats: Vec<_> = doc.iter().collect();
         let h1 = stats.iter().find(|s| s.heading() == Some("H1")).unwrap();
-        assert_eq!(
-            h1.subtree_paragraphs(),
-            Paragraphs {
-                count: 4,
-                max: 3,
-                total: 10,
-            }
-        );
+        let p = h1.subtree_paragraphs();
+        assert_eq!((p.count, p.max, p.total), (4, 3, 10));
 
         // A leaf section's subtree stats equal its own direct paragraphs.
         let h3 = stats.iter().find(|s| s.heading() == Some("H3")).unwrap();
@@ -426,15 +626,53 @@ mod tests {
     }
 
     #[test]
-    fn get_heading_is_case_insensitive_prefix_match() {
-        let doc = build("# Chapter One\n\nwords\n\n## Chapter Two\n\nmore words");
-        let found = doc.get_heading("CHAPTER TWO").unwrap();
-        assert_eq!(found.iter().next().unwrap().heading(), Some("Chapter Two"));
+    fn sections_remember_the_file_their_heading_came_from() {
+        let filter = crate::filter::TextFilter::new();
+        let mut builder = DocumentBuilder::new();
+        builder.set_source(Path::new("one.md"));
+        builder.apply(filter.lex("# One\n\nfirst"));
+        builder.set_source(Path::new("two.md"));
+        builder.apply(filter.lex("more for one\n\n# Two\n\nsecond"));
+        let doc = builder.finalize();
+        let sources: Vec<_> = doc.iter().map(|s| s.source()).collect();
+        assert_eq!(
+            sources,
+            vec![Some(Path::new("one.md")), Some(Path::new("two.md"))]
+        );
+        let lines: Vec<_> = doc.iter().map(|s| s.location().line).collect();
+        assert_eq!(lines, vec![1, 3]);
+    }
 
-        let prefix = doc.get_heading("CHAPTER O").unwrap();
-        assert_eq!(prefix.iter().next().unwrap().heading(), Some("Chapter One"));
+    #[test]
+    fn find_headings_returns_every_match_with_its_subsections() {
+        use crate::matcher::MatchMode;
 
-        assert!(doc.get_heading("NO SUCH HEADING").is_none());
+        let doc = build(
+            "# Chapter One\n\nwords\n\n## From the Book of Shadows\n\na b\n\n\
+             # Chapter Two\n\nmore words\n\n## From the Book of Shadows\n\nc d e",
+        );
+        let find = |mode, filter| {
+            let matcher = Matcher::new(mode, filter).unwrap();
+            let found: Vec<_> = doc
+                .find_headings(&matcher)
+                .into_iter()
+                .map(|x| x.stats().subtree_paragraphs().total)
+                .collect();
+            found
+        };
+
+        assert_eq!(find(MatchMode::Prefix, "chapter"), [3, 5]);
+        assert_eq!(find(MatchMode::Prefix, "CHAPTER T"), [5]);
+        assert_eq!(find(MatchMode::Substring, "shadows"), [2, 3]);
+        assert!(find(MatchMode::Prefix, "no such heading").is_empty());
+
+        let shadows = Matcher::new(MatchMode::Substring, "shadows").unwrap();
+        let combined = Document::combine(&doc.find_headings(&shadows));
+        assert_eq!(combined.stats().heading(), None);
+        assert_eq!(combined.stats().subtree_paragraphs().total, 5);
+
+        assert_eq!(find(MatchMode::Prefix, "chapter two > from"), [3]);
+        assert_eq!(find(MatchMode::Prefix, "* / *"), [2, 3]);
     }
 
     #[test]
@@ -11,4 +11,44 @@ pub enum Error {
 
     #[error("file not found: {0}")]
     FileNotFound(PathBuf),
+
+    #[error("`-` (standard input) can't be combined with other paths, --watch or --record")]
+    Stdin,
+
+    #[error("--distribution and --top only go with the table, not --format {0}")]
+    TableOnly(&'static str),
+
+    #[error("{path}: {source}")]
+    Config {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("bad noise pattern {name:?}: {source}")]
+    Noise { name: String, source: regex::Error },
+
+    #[error("unknown noise rule {name:?} (known rules: {known})")]
+    UnknownNoise { name: String, known: String },
+
+    #[error("bad heading filter {pattern:?}: {source}")]
+    HeadingPattern {
+        pattern: String,
+        source: regex::Error,
+    },
+
+    #[error("bad glob {pattern:?}: {source}")]
+    Glob {
+        pattern: String,
+        source: globset::Error,
+    },
+
+    #[error("no history found from {0}; record some with `ncount --record`")]
+    NoHistory(PathBuf),
+
+    #[error("{path}:{line}: bad history entry: {source}")]
+    HistoryEntry {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
 }
@@ -0,0 +1,79 @@
+use serde::Deserialize;
+
+/// Rates for turning word counts into estimates, as read from the `[rates]`
+/// table of `ncount.toml`:
+///
+/// ```toml
+/// reading = 238      # silent reading, words per minute
+/// narration = 155    # audiobook narration, words per minute
+/// page = 250         # words per standard manuscript page
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rates {
+    pub reading: u32,
+    pub narration: u32,
+    pub page: u32,
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Self {
+            reading: 238,
+            narration: 155,
+            page: 250,
+        }
+    }
+}
+
+impl Rates {
+    pub fn reading_minutes(&self, words: u32) -> u32 {
+        minutes(words, self.reading)
+    }
+
+    pub fn narration_minutes(&self, words: u32) -> u32 {
+        minutes(words, self.narration)
+    }
+
+    /// Manuscript pages; a partial page is still a page.
+    pub fn pages(&self, words: u32) -> u32 {
+        words.div_ceil(self.page.max(1))
+    }
+}
+
+fn minutes(words: u32, per_minute: u32) -> u32 {
+    (words as f64 / per_minute.max(1) as f64).round() as u32
+}
+
+/// `45m`, or `3h 05m` from an hour on.
+pub fn duration_label(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes:02}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_from_default_rates() {
+        let rates = Rates::default();
+        assert_eq!(rates.pages(90_000), 360);
+        assert_eq!(rates.pages(251), 2);
+        assert_eq!(rates.pages(0), 0);
+        assert_eq!(rates.reading_minutes(90_000), 378);
+        assert_eq!(rates.narration_minutes(90_000), 581);
+        assert_eq!(duration_label(rates.narration_minutes(90_000)), "9h 41m");
+        assert_eq!(duration_label(45), "45m");
+    }
+
+    #[test]
+    fn rates_are_configurable_one_at_a_time() {
+        let rates: Rates = toml::from_str("page = 300").unwrap();
+        assert_eq!(rates.page, 300);
+        assert_eq!(rates.reading, Rates::default().reading);
+        assert_eq!(rates.pages(600), 2);
+    }
+}
@@ -1,26 +1,144 @@
+use std::collections::VecDeque;
 use std::iter::Peekable;
 
 use compact_str::CompactString;
 use regex::{Matches, Regex};
 
-use crate::document::count_words;
+use crate::Result;
+use crate::counting::{Counting, Tally};
+use crate::document::{Location, ParagraphStats};
+use crate::error::Error;
+use crate::markup;
+
+/// The built-in noise rules: name, pattern, and whether it's on by default.
+const BUILT_IN: &[(&str, &str, bool)] = &[
+    // Inline notes: <note ...>
+    ("notes", r"<note.+?>", true),
+    // HTML comments: <!-- ... -->
+    ("comments", r"<!--(.|\n)+?-->", true),
+    // Footnote definitions and references: [^1]: ... and [^1]
+    ("footnotes", r"(?m:^\[\^[^\[]+\]:.+$)|\[\^[^\[]+\]", true),
+    // Placeholders: [[TK: ...]]
+    ("tk", r"(?s:\[\[TK\b.*?\]\])", false),
+    // Obsidian comments: %% ... %%
+    ("obsidian", r"(?s:%%.*?%%)", false),
+    // CriticMarkup comments: {>> ... <<}
+    ("critic", r"(?s:\{>>.*?<<\})", false),
+];
+
+/// The named rules for text that never counts. Starts out as the built-in
+/// rules, which custom ones can be added to and any of which can be turned
+/// on or off by name.
+#[derive(Clone, Debug)]
+pub struct Noise {
+    rules: Vec<NoiseRule>,
+}
+
+#[derive(Clone, Debug)]
+struct NoiseRule {
+    name: String,
+    pattern: String,
+    enabled: bool,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        let rules = BUILT_IN
+            .iter()
+            .map(|&(name, pattern, enabled)| NoiseRule {
+                name: name.to_string(),
+                pattern: pattern.to_string(),
+                enabled,
+            })
+            .collect();
+        Self { rules }
+    }
+}
+
+impl Noise {
+    /// Adds a rule, enabled, replacing any existing rule of the same name.
+    pub fn define(&mut self, name: &str, pattern: &str) {
+        let rule = NoiseRule {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            enabled: true,
+        };
+        match self.rules.iter_mut().find(|rule| rule.name == name) {
+            Some(existing) => *existing = rule,
+            None => self.rules.push(rule),
+        }
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        match self.rules.iter_mut().find(|rule| rule.name == name) {
+            Some(rule) => {
+                rule.enabled = enabled;
+                Ok(())
+            }
+            None => Err(Error::UnknownNoise {
+                name: name.to_string(),
+                known: self.names().collect::<Vec<_>>().join(", "),
+            }),
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.name.as_str())
+    }
+}
 
 pub struct TextFilter {
     tag: Regex,
+    indented_code: bool,
+    counting: Counting,
 }
 
 impl TextFilter {
+    /// The default noise rules only.
+    #[cfg(test)]
     pub fn new() -> Self {
-        // Footnotes:
-        // ^\[\^[^\[]+\]:.+$|\[\^[^\[]+\]
-        // HTML comment:
-        // <!--(.|\n)+?-->
-        // Inline notes:
-        // <note.+?>
-        Self {
-            tag: Regex::new(r"<note.+?>|<!--(.|\n)+?-->|(?m:^\[\^[^\[]+\]:.+$)|\[\^[^\[]+\]")
-                .unwrap(),
+        Self::with_noise(&Noise::default()).unwrap()
+    }
+
+    /// Skips whatever the enabled `noise` rules match. Each is checked on
+    /// its own first so a bad one is reported by name.
+    pub fn with_noise(noise: &Noise) -> Result<Self> {
+        let mut pattern = String::new();
+        for rule in noise.rules.iter().filter(|rule| rule.enabled) {
+            Regex::new(&rule.pattern).map_err(|source| Error::Noise {
+                name: rule.name.clone(),
+                source,
+            })?;
+            if !pattern.is_empty() {
+                pattern.push('|');
+            }
+            pattern.push_str("(?:");
+            pattern.push_str(&rule.pattern);
+            pattern.push(')');
+        }
+        // With every rule off, match nothing at all.
+        if pattern.is_empty() {
+            pattern.push_str(r"[^\s\S]");
         }
+        let tag = Regex::new(&pattern).map_err(|source| Error::Noise {
+            name: String::from("(combined)"),
+            source,
+        })?;
+        Ok(Self {
+            tag,
+            indented_code: false,
+            counting: Counting::default(),
+        })
+    }
+
+    /// Whether lines indented four spaces (or a tab) after a blank line are
+    /// code. Off by default: prose indents its paragraphs, too.
+    pub fn set_indented_code(&mut self, indented_code: bool) {
+        self.indented_code = indented_code;
+    }
+
+    pub fn set_counting(&mut self, counting: Counting) {
+        self.counting = counting;
     }
 
     /// Lexes `s` into a stream of heading/paragraph events, skipping
@@ -28,27 +146,60 @@ impl TextFilter {
     /// separate cleaned copy of the text first. A comment or footnote
     /// landing in the middle of a line does not split that line into two
     /// paragraphs: text is bundled together up to the next real
-    /// (non-removed) line break.
+    /// (non-removed) line break. Code blocks and front matter count for
+    /// nothing; code lines are reported as `LineEvent::Code`.
     pub fn lex<'a>(&'a self, s: &'a str) -> impl Iterator<Item = LineEvent> + 'a {
         Lines {
+            text: s,
+            start: None,
+            counted: 0,
+            newlines: 0,
             chunks: Chunks {
                 text: s,
                 matches: self.tag.find_iter(s).peekable(),
                 pos: 0,
             },
             current: None,
-            pending_mode: LineMode::Undecided,
-            pending_non_whitespace: false,
+            line: String::new(),
+            tally: Tally::default(),
+            link_open: false,
+            held: Vec::new(),
+            held_text: String::new(),
+            queued: VecDeque::new(),
+            block: Block::Text,
+            first_line: true,
+            indented_code: self.indented_code,
+            counting: self.counting,
         }
     }
 }
 
 /// One heading or one paragraph, already fully assembled from however many
-/// chunks it took to get there.
+/// chunks it took to get there, or one (non-blank) line of a code block.
 #[derive(Debug, Clone, PartialEq)]
 pub enum LineEvent {
-    Heading(CompactString, i32),
-    Paragraph(u32),
+    /// The heading's text, level, and where its line starts.
+    Heading(CompactString, i32, Location),
+    Paragraph(ParagraphStats),
+    Code,
+}
+
+/// What kind of text the lexer is in the middle of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Block {
+    Text,
+    /// A fenced code block, closed by at least `len` of the same `marker`.
+    Fence {
+        marker: u8,
+        len: usize,
+    },
+    /// An indented code block, closed by the first unindented line.
+    Indented,
+    /// The first line was `---` or `+++`, and a closing line follows
+    /// somewhere; it's front matter unless the next line is blank.
+    FrontMatterOpening(u8),
+    /// YAML (`---`) or TOML (`+++`) front matter.
+    FrontMatter(u8),
 }
 
 /// Walks `text`, skipping over `matches`, yielding the surviving text
@@ -90,12 +241,6 @@ impl<'a> Iterator for Chunks<'a> {
     }
 }
 
-enum LineMode {
-    Undecided,
-    Heading(CompactString),
-    Paragraph(u32),
-}
-
 /// Bundles a `Chunks` stream into complete heading/paragraph events, one per
 /// real line, using ordinary `str::lines()` over the bulk of each chunk (a
 /// well-optimized std primitive) and only doing the "does this paragraph
@@ -106,46 +251,316 @@ enum LineMode {
 /// doesn't, the match spliced two textual halves together inline (a comment
 /// sitting mid-sentence), so the next chunk's first line must be merged
 /// onto this chunk's dangling last line rather than treated separately.
+///
+/// Headings follow CommonMark: ATX (`## Scene`, optionally closed with
+/// `##`) and setext (lines underlined with `===` or `---`). The latter is
+/// why paragraph lines are held back until the line after them shows they
+/// aren't a heading's text.
+///
+/// Fenced code blocks (and, optionally, indented ones) are set apart the
+/// same way, as is YAML or TOML front matter at the very top of a file.
 struct Lines<'a> {
+    /// The whole text, for working out line numbers.
+    text: &'a str,
+    /// Where in `text` the line being assembled starts.
+    start: Option<usize>,
+    /// How much of `text` has been counted into `newlines`.
+    counted: usize,
+    newlines: u32,
     chunks: Chunks<'a>,
     current: Option<(Peekable<std::str::Lines<'a>>, bool)>,
-    pending_mode: LineMode,
-    pending_non_whitespace: bool,
+    /// The line being assembled, noise removed. Reused from line to line.
+    line: String,
+    /// `counting`'s tally of `line`, taken piece by piece as it's assembled
+    /// (so text on either side of a removed span doesn't run together).
+    tally: Tally,
+    /// Whether a link's label is open from an earlier line of the paragraph
+    /// (see `markup::strip_wrapped`).
+    link_open: bool,
+    /// The stats of the lines since the last blank line (or heading, or
+    /// code), while they might be a setext heading.
+    held: Vec<ParagraphStats>,
+    /// Their text, a space between lines.
+    held_text: String,
+    /// Events that have to wait their turn: held lines being released, and
+    /// whatever released them.
+    queued: VecDeque<LineEvent>,
+    block: Block,
+    /// Whether no line has been finished yet: front matter only opens there.
+    first_line: bool,
+    indented_code: bool,
+    counting: Counting,
 }
 
 impl Lines<'_> {
     fn absorb(&mut self, line: &str) {
-        if !line.trim().is_empty() {
-            self.pending_non_whitespace = true;
+        if self.start.is_none() {
+            self.start = Some(line.as_ptr() as usize - self.text.as_ptr() as usize);
+        }
+        self.line.push_str(line);
+        let text = markup::strip_wrapped(line, &mut self.link_open);
+        self.counting.tally(&mut self.tally, &text);
+    }
+
+    /// Classifies the assembled line, returning the event (if any) that
+    /// can be emitted now.
+    fn finish_line(&mut self) -> Option<LineEvent> {
+        let location = self.location();
+        let stats = ParagraphStats {
+            location,
+            ..self.counting.finish(std::mem::take(&mut self.tally))
+        };
+        let first_line = std::mem::take(&mut self.first_line);
+        // Only a paragraph line (below) passes an open link on.
+        let link_open = std::mem::take(&mut self.link_open);
+        if let Some(event) = self.finish_block_line() {
+            self.line.clear();
+            return event;
+        }
+
+        if self.line.trim().is_empty() {
+            self.line.clear();
+            return self.release();
+        }
+
+        if first_line
+            && let Some(marker) = front_matter_opener(&self.line)
+            && self.front_matter_closes(location.offset, marker)
+        {
+            self.block = Block::FrontMatterOpening(marker);
+            self.line.clear();
+            return None;
         }
-        match &mut self.pending_mode {
-            LineMode::Undecided => {
-                self.pending_mode = if line.starts_with('#') {
-                    LineMode::Heading(CompactString::from(line))
+
+        if let Some((marker, len)) = code_fence(&self.line) {
+            self.block = Block::Fence { marker, len };
+            self.line.clear();
+            return self.release();
+        }
+
+        // Unlike a fence, an indented block can't interrupt a paragraph.
+        if self.indented_code && self.held.is_empty() && is_indented(&self.line) {
+            self.block = Block::Indented;
+            self.line.clear();
+            return Some(LineEvent::Code);
+        }
+
+        if let Some((level, text)) = atx_heading(&self.line) {
+            let heading = LineEvent::Heading(heading_text(text), level, location);
+            self.line.clear();
+            return self.after_held(heading);
+        }
+
+        if let Some(level) = setext_underline(&self.line) {
+            self.line.clear();
+            let Some(first) = self.held.first() else {
+                // Not underlining anything: a thematic break, which counts
+                // as an (empty) paragraph like any other.
+                return Some(LineEvent::Paragraph(ParagraphStats {
+                    location,
+                    ..Default::default()
+                }));
+            };
+            let heading = LineEvent::Heading(heading_text(&self.held_text), level, first.location);
+            self.held.clear();
+            self.held_text.clear();
+            return Some(heading);
+        }
+
+        if !self.held_text.is_empty() {
+            self.held_text.push(' ');
+        }
+        self.held_text.push_str(self.line.trim());
+        self.line.clear();
+        self.held.push(stats);
+        self.link_open = link_open;
+        None
+    }
+
+    /// Handles the assembled line if it belongs to a code block or front
+    /// matter, returning `Some` with whatever it emits. A line that ends an
+    /// indented block isn't part of it, so that's left to the caller.
+    fn finish_block_line(&mut self) -> Option<Option<LineEvent>> {
+        let line = self.line.as_str();
+        let blank = line.trim().is_empty();
+        match self.block {
+            Block::Text => None,
+            Block::FrontMatterOpening(_) if blank => {
+                // Just a thematic break at the top of the file.
+                self.block = Block::Text;
+                Some(Some(LineEvent::Paragraph(ParagraphStats {
+                    location: Location { line: 1, offset: 0 },
+                    ..Default::default()
+                })))
+            }
+            Block::FrontMatterOpening(marker) | Block::FrontMatter(marker) => {
+                self.block = if front_matter_closer(line, marker) {
+                    Block::Text
                 } else {
-                    LineMode::Paragraph(count_words(line))
+                    Block::FrontMatter(marker)
                 };
+                Some(None)
+            }
+            Block::Fence { marker, len } => {
+                if code_fence(line).is_some_and(|(m, l)| m == marker && l >= len)
+                    && line
+                        .trim_start()
+                        .trim_start_matches(marker as char)
+                        .trim()
+                        .is_empty()
+                {
+                    self.block = Block::Text;
+                    Some(None)
+                } else if blank {
+                    Some(None)
+                } else {
+                    Some(Some(LineEvent::Code))
+                }
+            }
+            Block::Indented if blank => Some(None),
+            Block::Indented if is_indented(line) => Some(Some(LineEvent::Code)),
+            Block::Indented => {
+                self.block = Block::Text;
+                None
             }
-            LineMode::Heading(buf) => buf.push_str(line),
-            LineMode::Paragraph(count) => *count += count_words(line),
         }
     }
 
-    fn take_pending(&mut self) -> Option<LineEvent> {
-        let non_whitespace = std::mem::take(&mut self.pending_non_whitespace);
-        let mode = std::mem::replace(&mut self.pending_mode, LineMode::Undecided);
-        if !non_whitespace {
-            return None;
+    /// Whether a front matter opener starting at `start` has a closing line
+    /// somewhere after it. Without one, it's only a thematic break: the
+    /// rest of the file is text.
+    fn front_matter_closes(&self, start: usize, marker: u8) -> bool {
+        let rest = &self.text[start..];
+        rest.lines()
+            .skip(1)
+            .any(|line| front_matter_closer(line, marker))
+    }
+
+    /// Where the assembled line started: its line, counting from 1 (and
+    /// counting the lines of any noise removed before it), and the offset
+    /// of its first surviving byte.
+    fn location(&mut self) -> Location {
+        if let Some(start) = self.start.take() {
+            let skipped = &self.text.as_bytes()[self.counted..start];
+            self.newlines += skipped.iter().filter(|&&b| b == b'\n').count() as u32;
+            self.counted = start;
         }
-        Some(match mode {
-            LineMode::Heading(raw) => {
-                let level = raw.bytes().take_while(|&b| b == b'#').count() as i32;
-                let text = raw.trim_start_matches('#').trim();
-                LineEvent::Heading(CompactString::from(text), level)
-            }
-            LineMode::Paragraph(count) => LineEvent::Paragraph(count),
-            LineMode::Undecided => unreachable!("non_whitespace implies mode was set"),
-        })
+        Location {
+            line: self.newlines + 1,
+            offset: self.counted,
+        }
+    }
+
+    /// The held lines, as the paragraphs they turned out to be: the first
+    /// now, the rest queued.
+    fn release(&mut self) -> Option<LineEvent> {
+        self.held_text.clear();
+        self.queued
+            .extend(self.held.drain(..).map(LineEvent::Paragraph));
+        self.queued.pop_front()
+    }
+
+    /// `event`, once any held lines have been released ahead of it.
+    fn after_held(&mut self, event: LineEvent) -> Option<LineEvent> {
+        let released = self.release();
+        self.queued.push_back(event);
+        released.or_else(|| self.queued.pop_front())
+    }
+}
+
+/// The marker and length of a code fence: up to three spaces of indent,
+/// then three or more backticks or tildes. (A backtick fence's info string
+/// can't contain a backtick; that's inline code, not a fence.)
+fn code_fence(line: &str) -> Option<(u8, usize)> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 {
+        return None;
+    }
+    let marker = *rest
+        .as_bytes()
+        .first()
+        .filter(|&&b| b == b'`' || b == b'~')?;
+    let len = rest.bytes().take_while(|&b| b == marker).count();
+    if len < 3 || marker == b'`' && rest[len..].contains('`') {
+        return None;
+    }
+    Some((marker, len))
+}
+
+fn is_indented(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+/// `-` for a `---` (YAML) line, `+` for a `+++` (TOML) one.
+fn front_matter_opener(line: &str) -> Option<u8> {
+    match line.trim_end() {
+        "---" => Some(b'-'),
+        "+++" => Some(b'+'),
+        _ => None,
+    }
+}
+
+fn front_matter_closer(line: &str, marker: u8) -> bool {
+    matches!(
+        (marker, line.trim_end()),
+        (b'-', "---" | "...") | (b'+', "+++")
+    )
+}
+
+/// The level and text of an ATX heading: up to three spaces of indent, one
+/// to six `#`, then a space or tab (or nothing at all), with any closing
+/// run of `#` dropped.
+/// A heading as a reader sees it: markup stripped (`# *Emph* [Link](url)`
+/// is "Emph Link") and its spaces evened out.
+fn heading_text(text: &str) -> CompactString {
+    let mut heading = CompactString::default();
+    for word in markup::strip(text).split_whitespace() {
+        if !heading.is_empty() {
+            heading.push(' ');
+        }
+        heading.push_str(word);
+    }
+    heading
+}
+
+fn atx_heading(line: &str) -> Option<(i32, &str)> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 {
+        return None;
+    }
+    let level = rest.bytes().take_while(|&b| b == b'#').count();
+    let rest = &rest[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    let text = rest.trim();
+    let unclosed = text.trim_end_matches('#');
+    let text = if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        unclosed.trim_end()
+    } else {
+        // `# C#`: part of the text, not a closing sequence.
+        text
+    };
+    Some((level as i32, text))
+}
+
+/// The heading level a setext underline gives the line above it: `===` for
+/// 1, `---` for 2, with up to three spaces of indent.
+fn setext_underline(line: &str) -> Option<i32> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 {
+        return None;
+    }
+    let rest = rest.trim_end();
+    if rest.is_empty() {
+        None
+    } else if rest.bytes().all(|b| b == b'=') {
+        Some(1)
+    } else if rest.bytes().all(|b| b == b'-') {
+        Some(2)
+    } else {
+        None
     }
 }
 
@@ -153,6 +568,9 @@ impl<'a> Iterator for Lines<'a> {
     type Item = LineEvent;
 
     fn next(&mut self) -> Option<LineEvent> {
+        if let Some(event) = self.queued.pop_front() {
+            return Some(event);
+        }
         loop {
             if self.current.is_none() {
                 loop {
@@ -163,13 +581,18 @@ impl<'a> Iterator for Lines<'a> {
                             break;
                         }
                         Some(_empty) => continue,
-                        None => return self.take_pending(),
+                        None => {
+                            // Don't strand a held final line: release it
+                            // now, or on the next call if something else
+                            // was released first.
+                            return self.finish_line().or_else(|| self.release());
+                        }
                     }
                 }
             }
 
             // Pull one line out, ending the borrow on `self.current` before
-            // calling `absorb`/`take_pending` (both take `&mut self`).
+            // calling `absorb`/`finish_line` (both take `&mut self`).
             let (line, is_last, ends_with_nl) = {
                 let (lines, ends_with_nl) = self.current.as_mut().unwrap();
                 match lines.next() {
@@ -188,7 +611,7 @@ impl<'a> Iterator for Lines<'a> {
             self.absorb(line);
 
             if (!is_last || ends_with_nl)
-                && let Some(event) = self.take_pending()
+                && let Some(event) = self.finish_line()
             {
                 return Some(event);
             }
@@ -204,19 +627,36 @@ mod tests {
     use super::*;
 
     fn lex(s: &str) -> Vec<LineEvent> {
-        TextFilter::new().lex(s).collect()
+        words_only(TextFilter::new().lex(s))
+    }
+
+    /// The events with each paragraph reduced to its word count and each
+    /// heading to its text and level, which is all most of these tests are
+    /// about.
+    fn words_only(events: impl Iterator<Item = LineEvent>) -> Vec<LineEvent> {
+        events
+            .map(|event| match event {
+                LineEvent::Heading(text, level, _) => heading(&text, level),
+                LineEvent::Paragraph(stats) => para(stats.words),
+                LineEvent::Code => LineEvent::Code,
+            })
+            .collect()
+    }
+
+    fn para(words: u32) -> LineEvent {
+        LineEvent::Paragraph(ParagraphStats {
+            words,
+            ..Default::default()
+        })
     }
 
     fn heading(text: &str, level: i32) -> LineEvent {
-        LineEvent::Heading(CompactString::from(text), level)
+        LineEvent::Heading(CompactString::from(text), level, Location::default())
     }
 
     #[test]
     fn strips_html_comments() {
-        assert_eq!(
-            lex("before <!-- hidden --> after"),
-            vec![LineEvent::Paragraph(2)]
-        );
+        assert_eq!(lex("before <!-- hidden --> after"), vec![para(2)]);
     }
 
     #[test]
@@ -225,43 +665,37 @@ mod tests {
         // source lines must not fragment the paragraph it interrupts.
         assert_eq!(
             lex("word1 <!-- line one\nline two\nline three --> word2"),
-            vec![LineEvent::Paragraph(2)]
+            vec![para(2)]
         );
     }
 
     #[test]
     fn strips_footnote_definition_lines() {
         let input = "text[^note]\n\n[^note]: This whole line is a definition.\n\nmore text";
-        assert_eq!(
-            lex(input),
-            vec![LineEvent::Paragraph(1), LineEvent::Paragraph(2)]
-        );
+        assert_eq!(lex(input), vec![para(1), para(2)]);
     }
 
     #[test]
     fn strips_inline_footnote_references() {
-        assert_eq!(lex("word[^1] and word[^2]"), vec![LineEvent::Paragraph(3)]);
+        assert_eq!(lex("word[^1] and word[^2]"), vec![para(3)]);
     }
 
     #[test]
     fn strips_inline_notes() {
         let input = r#"before <note tag="foo" comment="bar"> after"#;
-        assert_eq!(lex(input), vec![LineEvent::Paragraph(2)]);
+        assert_eq!(lex(input), vec![para(2)]);
     }
 
     #[test]
     fn leaves_plain_text_untouched() {
-        assert_eq!(
-            lex("Don't stop, it's co-authored work."),
-            vec![LineEvent::Paragraph(6)]
-        );
+        assert_eq!(lex("Don't stop, it's co-authored work."), vec![para(6)]);
     }
 
     #[test]
     fn heading_interrupted_by_comment_reassembles_text() {
         assert_eq!(
             lex("# Chapter <!-- todo: rename --> One"),
-            vec![heading("Chapter  One", 1)]
+            vec![heading("Chapter One", 1)]
         );
     }
 
@@ -275,10 +709,7 @@ mod tests {
 
     #[test]
     fn blank_lines_are_skipped_not_emitted_as_empty_paragraphs() {
-        assert_eq!(
-            lex("first\n\n\n\nsecond"),
-            vec![LineEvent::Paragraph(1), LineEvent::Paragraph(1)]
-        );
+        assert_eq!(lex("first\n\n\n\nsecond"), vec![para(1), para(1)]);
     }
 
     #[test]
@@ -287,11 +718,7 @@ mod tests {
         // non-whitespace content, it just happens to contain no words.
         assert_eq!(
             lex("real words\n\n---\n\nmore words"),
-            vec![
-                LineEvent::Paragraph(2),
-                LineEvent::Paragraph(0),
-                LineEvent::Paragraph(2),
-            ]
+            vec![para(2), para(0), para(2),]
         );
     }
 
@@ -299,17 +726,259 @@ mod tests {
     fn multiple_headings_and_levels() {
         assert_eq!(
             lex("# One\n\ntext\n\n## Two\n\nmore text"),
+            vec![heading("One", 1), para(1), heading("Two", 2), para(2),]
+        );
+    }
+
+    #[test]
+    fn setext_underlines_turn_the_line_above_into_a_heading() {
+        assert_eq!(
+            lex("Part One\n========\n\ntext here\n\nScene\n---\nmore\nwords\n---\n"),
+            vec![
+                heading("Part One", 1),
+                para(2),
+                heading("Scene", 2),
+                heading("more words", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn setext_headings_can_span_lines() {
+        let text = "intro\n\nThe Long\n  and Winding\nRoad\n===\n\ntext\n";
+        assert_eq!(
+            lex(text),
+            vec![para(1), heading("The Long and Winding Road", 1), para(1)]
+        );
+        assert_eq!(locations(text), vec![(1, 0), (3, 7), (8, 40)]);
+
+        // Without an underline, each line is still its own paragraph.
+        assert_eq!(lex("one\ntwo three\n\n"), vec![para(1), para(2)]);
+    }
+
+    #[test]
+    fn underlines_with_nothing_to_underline_are_breaks() {
+        assert_eq!(
+            lex("===\n\n# One\n---\n"),
+            vec![para(0), heading("One", 1), para(0),]
+        );
+    }
+
+    #[test]
+    fn atx_closing_hashes_are_dropped() {
+        assert_eq!(
+            lex("## Scene ##\n### C# ###\n# Mono #\n#\n## ## \n"),
+            vec![
+                heading("Scene", 2),
+                heading("C#", 3),
+                heading("Mono", 1),
+                heading("", 1),
+                heading("", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn hashes_without_a_space_or_too_many_are_text() {
+        assert_eq!(
+            lex("#hashtag\n####### seven\n    # indented\n   # three\n"),
+            vec![para(1), para(1), para(1), heading("three", 1),]
+        );
+    }
+
+    #[test]
+    fn fenced_code_is_not_prose_or_headings() {
+        assert_eq!(
+            lex(
+                "Run it:\n```sh\n# install\nmake\n\n```\n\n~~~~\nnot ~~~ closed\n~~~\n~~~~~\ndone\n"
+            ),
             vec![
-                heading("One", 1),
-                LineEvent::Paragraph(1),
-                heading("Two", 2),
-                LineEvent::Paragraph(2),
+                para(2),
+                LineEvent::Code,
+                LineEvent::Code,
+                LineEvent::Code,
+                LineEvent::Code,
+                para(1),
             ]
         );
     }
 
+    #[test]
+    fn inline_backticks_do_not_open_a_fence() {
+        assert_eq!(
+            lex("```not a fence```
+"),
+            vec![para(3)]
+        );
+    }
+
+    #[test]
+    fn front_matter_at_the_top_is_skipped() {
+        let yaml = "---\ntitle: A Book\ntags: [one, two]\n...\n# One\n\ntext\n";
+        let toml = "+++\ntitle = \"A Book\"\n+++\n# One\n\ntext\n";
+        for text in [yaml, toml] {
+            assert_eq!(lex(text), vec![heading("One", 1), para(1)]);
+        }
+    }
+
+    #[test]
+    fn an_unclosed_opener_is_a_break_not_front_matter() {
+        assert_eq!(
+            lex("---\n# One\n\nIt was a dark and stormy night.\n\nShe ran.\n"),
+            vec![para(0), heading("One", 1), para(7), para(2)]
+        );
+        // `+++` isn't even a break, just text.
+        assert_eq!(
+            lex("+++\ntitle = 1\n---\n"),
+            vec![heading("+++ title = 1", 2)]
+        );
+    }
+
+    #[test]
+    fn front_matter_only_opens_on_the_first_line_before_text() {
+        assert_eq!(lex("---\n\nwords\n"), vec![para(0), para(1)]);
+        assert_eq!(
+            lex("\n---\ntitle: x\n---\n"),
+            vec![para(0), heading("title: x", 2)]
+        );
+    }
+
+    #[test]
+    fn indented_code_is_opt_in_and_cannot_interrupt_a_paragraph() {
+        let text = "Some text\n    still text\n\n    let x = 1;\n\n    x += 1;\nafter\n";
+        assert_eq!(
+            lex(text),
+            vec![para(2), para(2), para(3), para(2), para(1),]
+        );
+
+        let mut filter = TextFilter::new();
+        filter.set_indented_code(true);
+        let events = words_only(filter.lex(text));
+        assert_eq!(
+            events,
+            vec![para(2), para(2), LineEvent::Code, LineEvent::Code, para(1),]
+        );
+    }
+
+    #[test]
+    fn link_urls_and_markup_are_not_words() {
+        assert_eq!(
+            lex("Open [the door](https://example.com/a-very-long-url), <em>*now*</em>!\n"),
+            vec![para(4)]
+        );
+    }
+
+    #[test]
+    fn links_wrapped_across_lines_lose_their_urls() {
+        let url = "https://example.com/a/very/long/url/path-with-many-words";
+        assert_eq!(
+            lex(&format!("He opened [the\ndoor]({url}) slowly.\n")),
+            vec![para(3), para(2)]
+        );
+        // A blank line closes the paragraph, and any label with it.
+        assert_eq!(
+            lex(&format!("[the\n\ndoor]({url})\n")),
+            vec![para(1), para(11)]
+        );
+    }
+
+    #[test]
+    fn headings_lose_their_markup() {
+        assert_eq!(
+            lex("# *Emph* [Link](http://x.y/z)\n\nA <em>b</em>\n---\n"),
+            vec![heading("Emph Link", 1), heading("A b", 2)]
+        );
+    }
+
+    #[test]
+    fn paragraphs_carry_characters_sentences_and_dialogue() {
+        let events: Vec<_> = TextFilter::new()
+            .lex("\"Go.\" She went. <!-- aside -->\n")
+            .collect();
+        assert_eq!(
+            events,
+            vec![LineEvent::Paragraph(ParagraphStats {
+                words: 3,
+                chars: 16,
+                chars_no_spaces: 13,
+                sentences: 2,
+                dialogue_words: 1,
+                cjk_chars: 0,
+                location: Location { line: 1, offset: 0 },
+                by_characters: false,
+            })]
+        );
+    }
+
+    fn locations(text: &str) -> Vec<(u32, usize)> {
+        TextFilter::new()
+            .lex(text)
+            .filter_map(|event| match event {
+                LineEvent::Heading(_, _, location) => Some(location),
+                LineEvent::Paragraph(stats) => Some(stats.location),
+                LineEvent::Code => None,
+            })
+            .map(|location| (location.line, location.offset))
+            .collect()
+    }
+
+    #[test]
+    fn headings_and_paragraphs_know_where_they_start() {
+        let text = "# One\n\n<!-- a\nlong\ncomment -->\nfirst\n\n```\ncode\n```\nsecond <!-- x\n-->third\n***\n";
+        assert_eq!(locations(text), vec![(1, 0), (6, 31), (11, 51), (13, 74)]);
+
+        // A setext heading starts on the line it underlines.
+        let text = "intro\n\nTitle\n=====\n\n<!-- x --> body\n";
+        assert_eq!(locations(text), vec![(1, 0), (3, 7), (6, 30)]);
+    }
+
+    #[test]
+    fn optional_noise_rules_are_off_until_enabled() {
+        let text = "one [[TK: fix this]] two %% aside %% three {>> hm <<} <!-- x -->";
+        assert_eq!(lex(text), vec![para(8)]);
+
+        let mut noise = Noise::default();
+        for name in ["tk", "obsidian", "critic"] {
+            noise.set_enabled(name, true).unwrap();
+        }
+        let filter = TextFilter::with_noise(&noise).unwrap();
+        let events = words_only(filter.lex(text));
+        assert_eq!(events, vec![para(3)]);
+    }
+
+    #[test]
+    fn default_noise_rules_can_be_turned_off() {
+        let mut noise = Noise::default();
+        for name in ["notes", "comments", "footnotes"] {
+            noise.set_enabled(name, false).unwrap();
+        }
+        let filter = TextFilter::with_noise(&noise).unwrap();
+        let events = words_only(filter.lex("one <!-- two --> three"));
+        assert_eq!(events, vec![para(3)]);
+    }
+
+    #[test]
+    fn custom_noise_rules_are_skipped_alongside_the_defaults() {
+        let mut noise = Noise::default();
+        noise.define("todo", r"TODO\(.*?\)");
+        let filter = TextFilter::with_noise(&noise).unwrap();
+        let events = words_only(filter.lex("one TODO(fix this) two <!-- three -->"));
+        assert_eq!(events, vec![para(2)]);
+    }
+
+    #[test]
+    fn bad_noise_rules_are_reported_by_name() {
+        let mut noise = Noise::default();
+        noise.define("broken", "(unclosed");
+        let err = TextFilter::with_noise(&noise).err().unwrap();
+        assert!(matches!(err, Error::Noise { name, .. } if name == "broken"));
+
+        let err = noise.set_enabled("nope", true).unwrap_err();
+        assert!(matches!(err, Error::UnknownNoise { name, .. } if name == "nope"));
+    }
+
     #[test]
     fn final_line_without_trailing_newline_is_still_emitted() {
-        assert_eq!(lex("no trailing newline"), vec![LineEvent::Paragraph(3)]);
+        assert_eq!(lex("no trailing newline"), vec![para(3)]);
     }
 }
@@ -1,8 +1,13 @@
+mod delimited;
+mod distribution;
 mod heading;
+mod json;
 
 use std::{
     borrow::Cow,
+    env,
     io::{self, Write},
+    path::Path,
 };
 
 use heading::Heading;
@@ -12,13 +17,100 @@ use prettytable::{
     format::{Alignment, TableFormat},
 };
 
-use crate::document::{Document, DocumentStats, OverallStats};
+use crate::document::{Document, DocumentStats, OverallStats, Paragraphs};
+use crate::estimate::{Rates, duration_label};
+use crate::goals::{Goals, Progress};
+use crate::log;
+use crate::matcher::Matcher;
+
+/// How run-once mode writes its results to stdout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// the aligned stats table
+    #[default]
+    Table,
+    /// the full section tree as nested JSON
+    Json,
+    /// one comma-separated row per section
+    Csv,
+    /// one tab-separated row per section
+    Tsv,
+}
+
+/// The optional stats columns, shown in the order given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Column {
+    /// characters, spaces included
+    Chars,
+    /// characters, not counting whitespace
+    CharsNoSpaces,
+    /// number of sentences
+    Sentences,
+    /// average words per sentence
+    SentenceLength,
+    /// share of words inside quotation marks
+    Dialogue,
+    /// Chinese and Japanese characters
+    Cjk,
+    /// time to read silently
+    Reading,
+    /// time to narrate, as for an audiobook
+    Narration,
+    /// standard manuscript pages
+    Pages,
+    /// `file:line` where the section starts
+    Location,
+}
+
+impl Column {
+    pub fn label(self) -> &'static str {
+        match self {
+            Column::Chars => "Chars",
+            Column::CharsNoSpaces => "Chars -sp",
+            Column::Sentences => "Sent.",
+            Column::SentenceLength => "Avg sent.",
+            Column::Dialogue => "Dialogue",
+            Column::Cjk => "CJK",
+            Column::Reading => "Reading",
+            Column::Narration => "Narration",
+            Column::Pages => "Pages",
+            Column::Location => "Location",
+        }
+    }
+
+    /// The column's value for a row: its section's `paragraphs`, or for
+    /// `Location`, where the section starts (nothing for a total).
+    pub fn value(self, paragraphs: &Paragraphs, rates: &Rates, location: Option<&str>) -> String {
+        match self {
+            Column::Chars => paragraphs.chars.to_string(),
+            Column::CharsNoSpaces => paragraphs.chars_no_spaces.to_string(),
+            Column::Sentences => paragraphs.sentences.to_string(),
+            Column::SentenceLength => paragraphs.average_sentence_len().to_string(),
+            Column::Dialogue => format!("{}%", paragraphs.dialogue_percent()),
+            Column::Cjk => paragraphs.cjk_chars.to_string(),
+            Column::Reading => duration_label(rates.reading_minutes(paragraphs.total)),
+            Column::Narration => duration_label(rates.narration_minutes(paragraphs.total)),
+            Column::Pages => rates.pages(paragraphs.total).to_string(),
+            Column::Location => location.unwrap_or_default().to_string(),
+        }
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct StatFmt {
-    filter: Option<String>,
+    filter: Option<Matcher>,
     verbose: bool,
-    running_count: u32,
+    columns: Vec<Column>,
+    rates: Rates,
+    distribution: bool,
+    longest: Option<usize>,
+    output: OutputFormat,
+    goals: Goals,
+    daily: Option<Progress>,
+    /// Every section so far, for the Total column.
+    running: Paragraphs,
 }
 
 impl StatFmt {
@@ -26,62 +118,192 @@ impl StatFmt {
         Self {
             verbose,
             filter: None,
-            running_count: 0,
+            columns: Vec::new(),
+            rates: Rates::default(),
+            distribution: false,
+            longest: None,
+            output: OutputFormat::Table,
+            goals: Goals::default(),
+            daily: None,
+            running: Paragraphs::default(),
         }
     }
 
-    pub fn add_filter(&mut self, filter: impl Into<String>) {
-        self.filter = Some(filter.into());
+    pub fn add_filter(&mut self, filter: Matcher) {
+        self.filter = Some(filter);
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<Column>) {
+        self.columns = columns;
+    }
+
+    pub fn set_rates(&mut self, rates: Rates) {
+        self.rates = rates;
+    }
+
+    /// Paragraph length percentiles in place of the usual table.
+    pub fn set_distribution(&mut self, distribution: bool) {
+        self.distribution = distribution;
+    }
+
+    /// How many of the longest paragraphs to list after the table.
+    pub fn set_longest(&mut self, longest: Option<usize>) {
+        self.longest = longest;
+    }
+
+    pub fn set_output(&mut self, output: OutputFormat) {
+        self.output = output;
+    }
+
+    pub fn set_goals(&mut self, goals: Goals) {
+        self.goals = goals;
+    }
+
+    /// Today's words against the daily goal, printed under the table.
+    pub fn set_daily(&mut self, daily: Option<Progress>) {
+        self.daily = daily;
     }
 
     pub fn format(&mut self, document: &Document) -> io::Result<()> {
-        let document = self.apply_filter(document).unwrap_or(document);
-        self.format_document(document)
+        let filtered = self.apply_filter(document);
+        // The manuscript-wide goal only means something against the whole
+        // manuscript, not the filtered sections of it.
+        let total_goal = self.goals.total.filter(|_| filtered.is_none());
+        let document = filtered.as_ref().unwrap_or(document);
+        match self.output {
+            OutputFormat::Table => {
+                if self.distribution {
+                    distribution::write_table(io::stdout().lock(), document)?;
+                } else {
+                    self.format_document(document, total_goal)?;
+                }
+                match self.longest {
+                    Some(n) => distribution::write_longest(io::stdout().lock(), document, n),
+                    None => Ok(()),
+                }
+            }
+            OutputFormat::Json => json::write(io::stdout().lock(), document),
+            OutputFormat::Csv => delimited::write(io::stdout().lock(), document, b','),
+            OutputFormat::Tsv => delimited::write(io::stdout().lock(), document, b'\t'),
+        }
     }
 
-    fn format_document(&mut self, document: &Document) -> io::Result<()> {
-        let mut table = self.build_formatted_table();
+    fn format_document(&mut self, document: &Document, total_goal: Option<u32>) -> io::Result<()> {
+        let show_goals = self.goals.has_section_goals() || total_goal.is_some();
+        // Lines of code only get a column when there are any to report.
+        let show_code = self.verbose && document.iter().any(|stats| stats.code_lines() > 0);
+        let mut table = self.build_formatted_table(show_goals, show_code);
         for stats in document.iter() {
-            self.add_row(&mut table, stats);
+            self.add_row(&mut table, stats, show_code);
         }
 
-        if self.verbose {
+        if self.verbose || total_goal.is_some() {
             let sum: OverallStats = document.iter().collect();
             let row = table.add_empty_row();
             row.add_cell(Cell::new_align("", Alignment::LEFT));
-            row.add_cell(Cell::new_align(&sum.count.to_string(), Alignment::RIGHT));
-            row.add_cell(Cell::new_align(
-                &sum.average_len().to_string(),
-                Alignment::RIGHT,
-            ));
-            row.add_cell(Cell::new_align(&sum.max.to_string(), Alignment::RIGHT));
-            row.add_cell(Cell::new_align(&sum.total.to_string(), Alignment::RIGHT));
+            let p = sum.paragraphs;
+            if self.verbose {
+                row.add_cell(Cell::new_align(&p.count.to_string(), Alignment::RIGHT));
+                row.add_cell(Cell::new_align(
+                    &p.average_len().to_string(),
+                    Alignment::RIGHT,
+                ));
+                row.add_cell(Cell::new_align(&p.max.to_string(), Alignment::RIGHT));
+            }
+            for column in &self.columns {
+                row.add_cell(Cell::new_align(
+                    &column.value(&p, &self.rates, None),
+                    Alignment::RIGHT,
+                ));
+            }
+            if show_code {
+                row.add_cell(Cell::new_align(
+                    &sum.code_lines.to_string(),
+                    Alignment::RIGHT,
+                ));
+            }
+            row.add_cell(Cell::new_align(&p.total.to_string(), Alignment::RIGHT));
+            if let Some(target) = total_goal {
+                row.add_cell(Cell::new_align("", Alignment::RIGHT));
+                add_goal_cells(row, Progress::new(p.total, target));
+            }
         }
 
-        writeln!(io::stdout().lock(), "{table}")
+        let mut out = io::stdout().lock();
+        writeln!(out, "{table}")?;
+        if let Some(daily) = self.daily {
+            let line = format!(
+                "today: {} of {} words ({})",
+                daily.words,
+                daily.target,
+                daily.percent_label()
+            );
+            if daily.is_met() {
+                writeln!(out, "{}", line.green())?;
+            } else {
+                writeln!(out, "{line}")?;
+            }
+        }
+        Ok(())
     }
 
-    fn add_row(&mut self, table: &mut Table, stats: DocumentStats) {
+    fn add_row(&mut self, table: &mut Table, stats: DocumentStats, show_code: bool) {
         let row = table.add_empty_row();
+        let Some(heading) = heading_cell(stats) else {
+            return;
+        };
+        row.add_cell(heading);
+
+        // Goals are measured against the whole subtree, so a chapter whose
+        // words all live in its scenes still reports progress.
+        let progress = stats
+            .heading()
+            .and_then(|heading| self.goals.target(heading, stats.level()))
+            .map(|target| Progress::new(stats.subtree_paragraphs().total, target));
 
-        if let Some(heading) = stats.heading().map(Heading) {
-            let heading = match stats.level() {
-                0 | 1 => Cow::from(heading.to_string()),
-                2 => Cow::from(format!(" {heading}")),
-                3 => Cow::from(format!("  {heading}")),
-                4 => Cow::from(format!("   {heading}")),
-                _ => Cow::from(format!("    {heading}")),
+        let at = self
+            .columns
+            .contains(&Column::Location)
+            .then(|| location(stats.source(), stats.location().line));
+
+        let code_cell = || {
+            let code = stats.code_lines();
+            let code = if code > 0 {
+                code.to_string()
+            } else {
+                String::new()
             };
-            row.add_cell(Cell::new_align(&heading, Alignment::LEFT).style_spec("b"));
-        } else {
-            return;
-        }
+            Cell::new_align(&code, Alignment::RIGHT)
+        };
 
         if stats.paragraphs().is_zero() {
+            let has_code = show_code && stats.code_lines() > 0;
+            if progress.is_some() || has_code || at.is_some() {
+                let blanks = if self.verbose { 3 } else { 0 };
+                for _ in 0..blanks {
+                    row.add_cell(Cell::new_align("", Alignment::RIGHT));
+                }
+                for column in &self.columns {
+                    let value = match column {
+                        Column::Location => at.as_deref().unwrap_or_default(),
+                        _ => "",
+                    };
+                    row.add_cell(Cell::new_align(value, Alignment::RIGHT));
+                }
+                if show_code {
+                    row.add_cell(code_cell());
+                }
+            }
+            if let Some(progress) = progress {
+                for _ in 0..2 {
+                    row.add_cell(Cell::new_align("", Alignment::RIGHT));
+                }
+                add_goal_cells(row, progress);
+            }
             return;
         }
 
-        self.running_count += stats.paragraphs().total;
+        self.running.merge(stats.paragraphs());
 
         if self.verbose {
             row.add_cell(Cell::new_align(
@@ -97,39 +319,48 @@ impl StatFmt {
                 Alignment::RIGHT,
             ));
         }
+        for column in &self.columns {
+            row.add_cell(Cell::new_align(
+                &column.value(&stats.paragraphs(), &self.rates, at.as_deref()),
+                Alignment::RIGHT,
+            ));
+        }
+        if show_code {
+            row.add_cell(code_cell());
+        }
 
         row.add_cell(Cell::new_align(
             &stats.paragraphs().total.to_string(),
             Alignment::RIGHT,
         ));
         row.add_cell(Cell::new_align(
-            &self.running_count.to_string(),
+            &self.running.total.to_string(),
             Alignment::RIGHT,
         ));
+
+        if let Some(progress) = progress {
+            add_goal_cells(row, progress);
+        }
     }
 
-    fn apply_filter<'a>(&self, document: &'a Document) -> Option<&'a Document> {
-        let heading = self.filter.as_ref()?;
-        let found = document.get_heading(&heading.to_ascii_uppercase());
-        if found.is_none() {
-            eprintln!(
-                "{}",
-                format!("warning: no heading matching {heading:?} found; showing everything")
-                    .yellow()
-            );
+    /// Every section the filter matches, together, or `None` to show the
+    /// whole document.
+    fn apply_filter(&self, document: &Document) -> Option<Document> {
+        let matcher = self.filter.as_ref()?;
+        let found = document.find_headings(matcher);
+        if found.is_empty() {
+            log::warn(format_args!(
+                "no heading matching {:?} found; showing everything",
+                matcher.filter()
+            ));
+            return None;
         }
-        found
+        Some(Document::combine(&found))
     }
 
     /// Builds a table with appropriate format and headers.
-    fn build_formatted_table(&self) -> Table {
-        let mut format = TableFormat::new();
-        format.borders(' ');
-        format.padding(0, 3);
-
-        let mut table = Table::new();
-        table.set_format(format);
-
+    fn build_formatted_table(&self, show_goals: bool, show_code: bool) -> Table {
+        let mut table = new_table();
         let row = table.add_empty_row();
         row.add_cell(Cell::new_align("§", Alignment::LEFT));
         if self.verbose {
@@ -137,9 +368,70 @@ impl StatFmt {
             row.add_cell(Cell::new_align("Avg ¶", Alignment::RIGHT));
             row.add_cell(Cell::new_align("Long ¶", Alignment::RIGHT));
         }
+        for column in &self.columns {
+            row.add_cell(Cell::new_align(column.label(), Alignment::RIGHT));
+        }
+        if show_code {
+            row.add_cell(Cell::new_align("Code", Alignment::RIGHT));
+        }
         row.add_cell(Cell::new_align("Words", Alignment::RIGHT));
         row.add_cell(Cell::new_align("Total", Alignment::RIGHT));
+        if show_goals {
+            row.add_cell(Cell::new_align("Goal", Alignment::RIGHT));
+            row.add_cell(Cell::new_align("%", Alignment::RIGHT));
+            row.add_cell(Cell::new_align("Left", Alignment::RIGHT));
+        }
 
         table
     }
 }
+
+/// An empty table in the house style: no rules, just padding.
+pub(crate) fn new_table() -> Table {
+    let mut format = TableFormat::new();
+    format.borders(' ');
+    format.padding(0, 3);
+
+    let mut table = Table::new();
+    table.set_format(format);
+    table
+}
+
+/// The section's heading, in bold and indented by level; `None` for the
+/// untitled text before the first heading.
+fn heading_cell(stats: DocumentStats) -> Option<Cell> {
+    let heading = Heading(stats.heading()?);
+    let heading = match stats.level() {
+        0 | 1 => Cow::from(heading.to_string()),
+        2 => Cow::from(format!(" {heading}")),
+        3 => Cow::from(format!("  {heading}")),
+        4 => Cow::from(format!("   {heading}")),
+        _ => Cow::from(format!("    {heading}")),
+    };
+    Some(Cell::new_align(&heading, Alignment::LEFT).style_spec("b"))
+}
+
+/// `file:line`, with the file relative to the current directory where it
+/// can be, or just the line when the file isn't known (standard input).
+pub(crate) fn location(source: Option<&Path>, line: u32) -> String {
+    let Some(source) = source else {
+        return format!("line {line}");
+    };
+    let relative = env::current_dir()
+        .ok()
+        .and_then(|cwd| source.strip_prefix(cwd).ok().map(Path::to_path_buf));
+    format!("{}:{line}", relative.as_deref().unwrap_or(source).display())
+}
+
+/// Goal, percent complete, and words left (`+N` once over), in green once
+/// the goal is met.
+fn add_goal_cells(row: &mut prettytable::Row, progress: Progress) {
+    // `style_spec` replaces the alignment too, hence the explicit `r`.
+    let style = if progress.is_met() { "rFg" } else { "r" };
+    row.add_cell(Cell::new_align(
+        &progress.target.to_string(),
+        Alignment::RIGHT,
+    ));
+    row.add_cell(Cell::new(&progress.percent_label()).style_spec(style));
+    row.add_cell(Cell::new(&progress.left_label()).style_spec(style));
+}
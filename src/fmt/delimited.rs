@@ -0,0 +1,147 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::document::{Document, DocumentStats, PATH_SEPARATOR, Paragraphs};
+
+const HEADER: [&str; 10] = [
+    "heading", "level", "count", "average", "longest", "words", "total", "code", "source", "line",
+];
+
+/// Writes one row per section (the same sections, in the same order, as
+/// the table) separated by `delimiter`. Unlike the table, sections with no
+/// paragraphs still get a row, with zeroes, so every heading is present.
+/// Each row ends with the file and line its heading is on (the file empty
+/// for standard input).
+pub fn write(mut out: impl Write, document: &Document, delimiter: u8) -> io::Result<()> {
+    let mut writer = RowWriter {
+        out: &mut out,
+        delimiter,
+        running: Paragraphs::default(),
+    };
+    writer.record(HEADER.map(Cow::from))?;
+
+    let stats = document.stats();
+    if stats.heading().is_some() {
+        writer.section(stats, &mut Vec::new())
+    } else {
+        stats
+            .children()
+            .try_for_each(|child| writer.section(child, &mut Vec::new()))
+    }
+}
+
+struct RowWriter<W> {
+    out: W,
+    delimiter: u8,
+    running: Paragraphs,
+}
+
+impl<W: Write> RowWriter<W> {
+    fn section<'a>(&mut self, stats: DocumentStats<'a>, path: &mut Vec<&'a str>) -> io::Result<()> {
+        let Some(heading) = stats.heading() else {
+            return Ok(());
+        };
+        path.push(heading);
+
+        let p = stats.paragraphs();
+        self.running.merge(p);
+        let average = if p.is_zero() { 0 } else { p.average_len() };
+        self.record([
+            Cow::from(path.join(PATH_SEPARATOR)),
+            Cow::from(stats.level().to_string()),
+            Cow::from(p.count.to_string()),
+            Cow::from(average.to_string()),
+            Cow::from(p.max.to_string()),
+            Cow::from(p.total.to_string()),
+            Cow::from(self.running.total.to_string()),
+            Cow::from(stats.code_lines().to_string()),
+            stats
+                .source()
+                .map(|source| source.to_string_lossy())
+                .unwrap_or_default(),
+            Cow::from(stats.location().line.to_string()),
+        ])?;
+
+        for child in stats.children() {
+            self.section(child, path)?;
+        }
+        path.pop();
+        Ok(())
+    }
+
+    fn record<'s>(&mut self, fields: impl IntoIterator<Item = Cow<'s, str>>) -> io::Result<()> {
+        for (i, field) in fields.into_iter().enumerate() {
+            if i > 0 {
+                self.out.write_all(&[self.delimiter])?;
+            }
+            self.out.write_all(self.escape(&field).as_bytes())?;
+        }
+        writeln!(self.out)
+    }
+
+    /// CSV quotes any field containing the delimiter, a quote, or a line
+    /// break, doubling embedded quotes (RFC 4180). TSV has no quoting at
+    /// all, so tabs and line breaks are flattened to spaces instead.
+    fn escape<'s>(&self, field: &'s str) -> Cow<'s, str> {
+        if self.delimiter == b'\t' {
+            if field.contains(['\t', '\n', '\r']) {
+                return Cow::from(field.replace(['\t', '\n', '\r'], " "));
+            }
+            return Cow::from(field);
+        }
+
+        if field.contains([self.delimiter as char, '"', '\n', '\r']) {
+            Cow::from(format!("\"{}\"", field.replace('"', "\"\"")))
+        } else {
+            Cow::from(field)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::DocumentBuilder;
+    use crate::filter::TextFilter;
+
+    fn render(text: &str, delimiter: u8) -> String {
+        let mut builder = DocumentBuilder::new();
+        builder.apply(TextFilter::new().lex(text));
+        let mut out = Vec::new();
+        write(&mut out, &builder.finalize(), delimiter).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn csv_rows_carry_paths_stats_and_running_totals() {
+        let csv = render(
+            "# One\n\na b c\n\nd\n\n## Empty\n\n## Child\n\ne f\n\n```\nlet g;\n```",
+            b',',
+        );
+        assert_eq!(
+            csv,
+            "heading,level,count,average,longest,words,total,code,source,line\n\
+             One,1,2,2,3,4,4,0,,1\n\
+             One > Empty,2,0,0,0,0,4,0,,7\n\
+             One > Child,2,1,2,2,2,6,1,,9\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_headings_with_commas_and_quotes() {
+        let csv = render("# Well, \"Actually\"\n\nwords", b',');
+        assert_eq!(
+            csv.lines().nth(1),
+            Some(r#""Well, ""Actually""",1,1,1,1,1,1,0,,1"#)
+        );
+    }
+
+    #[test]
+    fn tsv_leaves_commas_alone() {
+        let tsv = render("# Well, then\n\nwords", b'\t');
+        assert_eq!(
+            tsv.lines().nth(1),
+            Some("Well, then\t1\t1\t1\t1\t1\t1\t0\t\t1")
+        );
+    }
+}
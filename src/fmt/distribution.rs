@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+
+use prettytable::{Cell, format::Alignment};
+
+use crate::document::{Document, DocumentStats, PATH_SEPARATOR, ParagraphRecord};
+
+use super::{heading_cell, location, new_table};
+
+/// `--distribution`: how paragraph lengths are spread within each section,
+/// instead of the usual table. The median is the typical paragraph; P90 is
+/// the length only one paragraph in ten goes past.
+pub fn write_table(mut out: impl Write, document: &Document) -> io::Result<()> {
+    let mut table = new_table();
+    let row = table.add_empty_row();
+    row.add_cell(Cell::new_align("§", Alignment::LEFT));
+    for label in ["Count ¶", "Median", "P90", "Long ¶"] {
+        row.add_cell(Cell::new_align(label, Alignment::RIGHT));
+    }
+
+    let mut all = Vec::new();
+    for stats in document.iter() {
+        let Some(heading) = heading_cell(stats) else {
+            continue;
+        };
+        let mut lengths = lengths(stats.records());
+        let row = table.add_empty_row();
+        row.add_cell(heading);
+        if !lengths.is_empty() {
+            add_spread_cells(row, &lengths);
+        }
+        all.append(&mut lengths);
+    }
+
+    all.sort_unstable();
+    let row = table.add_empty_row();
+    row.add_cell(Cell::new_align("", Alignment::LEFT));
+    add_spread_cells(row, &all);
+
+    writeln!(out, "{table}")
+}
+
+/// `--top N`: the `n` longest paragraphs, longest first, with where to find
+/// them.
+pub fn write_longest(mut out: impl Write, document: &Document, n: usize) -> io::Result<()> {
+    let mut found = Vec::new();
+    let stats = document.stats();
+    if stats.heading().is_some() {
+        collect(stats, &mut Vec::new(), &mut found);
+    } else {
+        // Untitled text before the first heading is still text.
+        for record in stats.records() {
+            found.push((record, String::new()));
+        }
+        for child in stats.children() {
+            collect(child, &mut Vec::new(), &mut found);
+        }
+    }
+    found.sort_by(|(a, _), (b, _)| {
+        b.words
+            .cmp(&a.words)
+            .then_with(|| a.source.cmp(&b.source))
+            .then_with(|| a.location.line.cmp(&b.location.line))
+    });
+
+    let mut table = new_table();
+    let row = table.add_empty_row();
+    row.add_cell(Cell::new_align("Words", Alignment::RIGHT));
+    row.add_cell(Cell::new_align("Location", Alignment::LEFT));
+    row.add_cell(Cell::new_align("§", Alignment::LEFT));
+    for (record, path) in found.into_iter().take(n) {
+        let row = table.add_empty_row();
+        row.add_cell(Cell::new_align(&record.words.to_string(), Alignment::RIGHT));
+        row.add_cell(Cell::new_align(
+            &location(record.source.as_deref(), record.location.line),
+            Alignment::LEFT,
+        ));
+        row.add_cell(Cell::new_align(&path, Alignment::LEFT));
+    }
+    writeln!(out, "{table}")
+}
+
+fn collect<'a>(
+    stats: DocumentStats<'a>,
+    path: &mut Vec<&'a str>,
+    found: &mut Vec<(&'a ParagraphRecord, String)>,
+) {
+    path.push(stats.heading().unwrap_or_default());
+    let joined = path.join(PATH_SEPARATOR);
+    for record in stats.records() {
+        found.push((record, joined.clone()));
+    }
+    for child in stats.children() {
+        collect(child, path, found);
+    }
+    path.pop();
+}
+
+/// Word counts, shortest first.
+fn lengths(records: &[ParagraphRecord]) -> Vec<u32> {
+    let mut lengths: Vec<_> = records.iter().map(|record| record.words).collect();
+    lengths.sort_unstable();
+    lengths
+}
+
+fn add_spread_cells(row: &mut prettytable::Row, sorted: &[u32]) {
+    let values = [
+        sorted.len() as u32,
+        percentile(sorted, 50),
+        percentile(sorted, 90),
+        sorted.last().copied().unwrap_or_default(),
+    ];
+    for value in values {
+        row.add_cell(Cell::new_align(&value.to_string(), Alignment::RIGHT));
+    }
+}
+
+/// The nearest-rank percentile of `sorted`: the smallest value at least `p`
+/// percent of the values are no greater than.
+fn percentile(sorted: &[u32], p: usize) -> u32 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::document::DocumentBuilder;
+    use crate::filter::TextFilter;
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let sorted: Vec<u32> = (1..=10).collect();
+        assert_eq!(percentile(&sorted, 50), 5);
+        assert_eq!(percentile(&sorted, 90), 9);
+        assert_eq!(percentile(&[7], 90), 7);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+
+    #[test]
+    fn longest_paragraphs_come_with_their_locations() {
+        let filter = TextFilter::new();
+        let mut builder = DocumentBuilder::new();
+        builder.set_source(Path::new("one.md"));
+        builder.apply(filter.lex("# One\n\na b c\n\nd\n"));
+        builder.set_source(Path::new("two.md"));
+        builder.apply(filter.lex("more of one\n\n## Two\n\ne f g h\n"));
+
+        let mut out = Vec::new();
+        write_longest(&mut out, &builder.finalize(), 3).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let rows: Vec<_> = out
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|row| !row.is_empty())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                vec!["4", "two.md:5", "One", ">", "Two"],
+                vec!["3", "one.md:3", "One"],
+                vec!["3", "two.md:1", "One"],
+            ]
+        );
+    }
+}
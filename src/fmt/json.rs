@@ -0,0 +1,151 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::document::{Document, DocumentStats, Location, OverallStats, Paragraphs};
+
+/// The top level of `--format json`: the sections being reported, plus
+/// their combined paragraph summary (what the table's closing row shows:
+/// text before the first heading isn't in any section, so it isn't in it).
+#[derive(Serialize)]
+struct Report<'a> {
+    paragraphs: Paragraphs,
+    sections: Vec<Section<'a>>,
+}
+
+/// One heading and everything under it. `line` and `offset` say where the
+/// heading is in `source`. `running_total` is the table's Total column: the
+/// words of every section up to and including this one, in document order.
+#[derive(Serialize)]
+struct Section<'a> {
+    heading: &'a str,
+    level: i32,
+    source: Option<&'a Path>,
+    #[serde(flatten)]
+    location: Location,
+    paragraphs: Paragraphs,
+    subtree_paragraphs: Paragraphs,
+    running_total: u32,
+    code_lines: u32,
+    sections: Vec<Section<'a>>,
+}
+
+pub fn write(mut out: impl Write, document: &Document) -> io::Result<()> {
+    let report = build_report(document);
+    serde_json::to_writer_pretty(&mut out, &report)?;
+    writeln!(out)
+}
+
+fn build_report(document: &Document) -> Report<'_> {
+    let stats = document.stats();
+    let mut running = Paragraphs::default();
+    let sections = if stats.heading().is_some() {
+        vec![build_section(stats, &mut running)]
+    } else {
+        stats
+            .children()
+            .map(|child| build_section(child, &mut running))
+            .collect()
+    };
+
+    Report {
+        paragraphs: document.iter().collect::<OverallStats>().paragraphs,
+        sections,
+    }
+}
+
+fn build_section<'a>(stats: DocumentStats<'a>, running: &mut Paragraphs) -> Section<'a> {
+    running.merge(stats.paragraphs());
+    let running_total = running.total;
+    Section {
+        heading: stats.heading().unwrap_or_default(),
+        level: stats.level(),
+        source: stats.source(),
+        location: stats.location(),
+        paragraphs: stats.paragraphs(),
+        subtree_paragraphs: stats.subtree_paragraphs(),
+        running_total,
+        code_lines: stats.code_lines(),
+        sections: stats
+            .children()
+            .map(|child| build_section(child, running))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::document::DocumentBuilder;
+    use crate::filter::TextFilter;
+    use crate::matcher::{MatchMode, Matcher};
+
+    fn render(text: &str) -> Value {
+        let mut builder = DocumentBuilder::new();
+        builder.set_source(Path::new("book.md"));
+        builder.apply(TextFilter::new().lex(text));
+        let mut out = Vec::new();
+        write(&mut out, &builder.finalize()).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn nests_sections_and_carries_running_totals() {
+        let value = render("# One\n\na b\n\n## Child\n\nc d e\n\n# Two\n\nf");
+
+        assert_eq!(
+            value["paragraphs"],
+            json!({
+                "count": 3,
+                "max": 3,
+                "total": 6,
+                "chars": 9,
+                "chars_no_spaces": 6,
+                "sentences": 3,
+                "dialogue_words": 0,
+                "cjk_chars": 0,
+            })
+        );
+        let one = &value["sections"][0];
+        assert_eq!(one["heading"], "One");
+        assert_eq!(one["level"], 1);
+        assert_eq!(one["source"], "book.md");
+        assert_eq!((&one["line"], &one["offset"]), (&json!(1), &json!(0)));
+        assert_eq!(one["paragraphs"]["total"], 2);
+        assert_eq!(one["subtree_paragraphs"]["total"], 5);
+        assert_eq!(one["running_total"], 2);
+        assert_eq!(one["sections"][0]["heading"], "Child");
+        assert_eq!(one["sections"][0]["running_total"], 5);
+        assert_eq!(one["sections"][0]["line"], 5);
+        assert_eq!(one["sections"][0]["offset"], 12);
+        assert_eq!(value["sections"][1]["running_total"], 6);
+    }
+
+    #[test]
+    fn a_filtered_root_is_its_own_single_section() {
+        let mut builder = DocumentBuilder::new();
+        builder.apply(TextFilter::new().lex("# One\n\na\n\n## Child\n\nb c"));
+        let document = builder.finalize();
+        let matcher = Matcher::new(MatchMode::Prefix, "child").unwrap();
+        let child = Document::combine(&document.find_headings(&matcher));
+
+        let mut out = Vec::new();
+        write(&mut out, &child).unwrap();
+        let value: Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(value["sections"].as_array().unwrap().len(), 1);
+        assert_eq!(value["sections"][0]["heading"], "Child");
+        assert_eq!(value["sections"][0]["source"], Value::Null);
+        assert_eq!(value["paragraphs"]["total"], 2);
+    }
+
+    #[test]
+    fn the_summary_leaves_out_text_before_the_first_heading() {
+        let value = render("a title page\n\n# One\n\nb c");
+        assert_eq!(value["paragraphs"]["total"], 2);
+        assert_eq!(value["sections"][0]["running_total"], 2);
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::Result;
+use crate::error::Error;
+
+/// Word-count targets, as read from a `--goals` file (or the `[goals]`
+/// table of `ncount.toml`):
+///
+/// ```toml
+/// total = 90000          # the whole manuscript
+/// daily = 1500           # words written today (needs `--record` history)
+///
+/// [levels]
+/// 1 = 3000               # every level-1 section, i.e. every chapter
+///
+/// [headings]
+/// "Chapter III: The Prince" = 5000
+/// ```
+///
+/// A section's goal is measured against its whole subtree, so a chapter's
+/// target counts the scenes under it.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Goals {
+    pub total: Option<u32>,
+    pub daily: Option<u32>,
+    #[serde(default)]
+    levels: HashMap<i32, u32>,
+    /// Keyed by lowercased heading, so lookups are case-insensitive.
+    #[serde(default, deserialize_with = "lowercase_keys")]
+    headings: HashMap<String, u32>,
+}
+
+fn lowercase_keys<'de, D>(deserializer: D) -> Result<HashMap<String, u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let headings = HashMap::<String, u32>::deserialize(deserializer)?;
+    Ok(headings
+        .into_iter()
+        .map(|(heading, target)| (heading.to_lowercase(), target))
+        .collect())
+}
+
+impl Goals {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|source| Error::Config {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// True when some section may have a target of its own. Without one,
+    /// per-section goal columns would all be blank.
+    pub fn has_section_goals(&self) -> bool {
+        !self.levels.is_empty() || !self.headings.is_empty()
+    }
+
+    /// The target for one section: its own heading's, if it has one,
+    /// otherwise its level's.
+    pub fn target(&self, heading: &str, level: i32) -> Option<u32> {
+        self.headings
+            .get(&heading.to_lowercase())
+            .or_else(|| self.levels.get(&level))
+            .copied()
+            .filter(|&target| target > 0)
+    }
+}
+
+/// Words written against a (nonzero) target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub words: u32,
+    pub target: u32,
+}
+
+impl Progress {
+    pub fn new(words: u32, target: u32) -> Self {
+        Self { words, target }
+    }
+
+    pub fn percent(&self) -> u32 {
+        (self.words as u64 * 100 / self.target.max(1) as u64) as u32
+    }
+
+    pub fn is_met(&self) -> bool {
+        self.words >= self.target
+    }
+
+    /// `87%`
+    pub fn percent_label(&self) -> String {
+        format!("{}%", self.percent())
+    }
+
+    /// Words still to write, or, once the goal is met, how far past it
+    /// with a leading `+` (`1200` short vs. `+340` over).
+    pub fn left_label(&self) -> String {
+        if self.is_met() {
+            format!("+{}", self.words - self.target)
+        } else {
+            (self.target - self.words).to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goals(text: &str) -> Goals {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("goals.toml");
+        fs::write(&path, text).unwrap();
+        Goals::load(&path).unwrap()
+    }
+
+    #[test]
+    fn headings_override_levels_case_insensitively() {
+        let goals =
+            goals("total = 90000\n[levels]\n1 = 3000\n[headings]\n\"Chapter III\" = 5000\n");
+        assert_eq!(goals.total, Some(90000));
+        assert_eq!(goals.target("chapter iii", 1), Some(5000));
+        assert_eq!(goals.target("Chapter II", 1), Some(3000));
+        assert_eq!(goals.target("2.1", 2), None);
+    }
+
+    #[test]
+    fn unknown_keys_are_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("goals.toml");
+        fs::write(&path, "totl = 90000\n").unwrap();
+        assert!(matches!(Goals::load(&path), Err(Error::Config { .. })));
+    }
+
+    #[test]
+    fn progress_labels_show_remaining_then_overage() {
+        let under = Progress::new(2610, 3000);
+        assert_eq!(under.percent_label(), "87%");
+        assert_eq!(under.left_label(), "390");
+
+        let over = Progress::new(3340, 3000);
+        assert!(over.is_met());
+        assert_eq!(over.percent_label(), "111%");
+        assert_eq!(over.left_label(), "+340");
+    }
+}
@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use jiff::Timestamp;
+use jiff::tz::TimeZone;
+use prettytable::{Cell, Table, format::Alignment};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::cli::{Grouping, HistoryArgs, pattern_base_dir};
+use crate::document::{Document, DocumentStats, PATH_SEPARATOR};
+use crate::error::Error;
+use crate::fmt;
+
+const STORE_DIR: &str = ".ncount";
+const STORE_FILE: &str = "history.jsonl";
+
+/// One `--record`ed run: the words in every section at a moment in time.
+/// Stored one per line, oldest first, in `.ncount/history.jsonl`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: Timestamp,
+    /// Words across every section, i.e. the table's final running total.
+    pub total: u32,
+    pub sections: Vec<SectionTotal>,
+}
+
+/// A section's own words (not its subtree's), keyed by its heading path.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SectionTotal {
+    pub path: String,
+    pub words: u32,
+}
+
+impl Snapshot {
+    pub fn new(timestamp: Timestamp, document: &Document) -> Self {
+        let mut sections = Vec::new();
+        let stats = document.stats();
+        if stats.heading().is_some() {
+            collect_sections(stats, &mut Vec::new(), &mut sections);
+        } else {
+            for child in stats.children() {
+                collect_sections(child, &mut Vec::new(), &mut sections);
+            }
+        }
+
+        Self {
+            timestamp,
+            total: sections.iter().map(|s| s.words).sum(),
+            sections,
+        }
+    }
+}
+
+fn collect_sections<'a>(
+    stats: DocumentStats<'a>,
+    path: &mut Vec<&'a str>,
+    sections: &mut Vec<SectionTotal>,
+) {
+    let Some(heading) = stats.heading() else {
+        return;
+    };
+    path.push(heading);
+    sections.push(SectionTotal {
+        path: path.join(PATH_SEPARATOR),
+        words: stats.paragraphs().total,
+    });
+    for child in stats.children() {
+        collect_sections(child, path, sections);
+    }
+    path.pop();
+}
+
+/// Appends a snapshot of `document` to the history log of the directory
+/// holding all of `files`, creating the log if need be. Returns the log's
+/// path.
+pub fn record(files: &[PathBuf], document: &Document) -> Result<PathBuf> {
+    let path = store_path(files);
+    if let Some(store) = path.parent() {
+        fs::create_dir_all(store)?;
+    }
+
+    let mut line = serde_json::to_string(&Snapshot::new(Timestamp::now(), document))
+        .map_err(io::Error::from)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?
+        .write_all(line.as_bytes())?;
+    Ok(path)
+}
+
+/// Words written today: the current total against where the history log
+/// stood before today began (or at today's first snapshot, if it starts
+/// today). `None` when there is no history to measure against.
+pub fn written_today(files: &[PathBuf], document: &Document) -> Result<Option<u32>> {
+    let path = store_path(files);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let tz = TimeZone::system();
+    let today = Timestamp::now().to_zoned(tz.clone()).date();
+    let snapshots = load(&path)?;
+    let baseline = snapshots
+        .iter()
+        .rev()
+        .find(|s| s.timestamp.to_zoned(tz.clone()).date() < today)
+        .or_else(|| snapshots.first())
+        .map(|s| s.total);
+
+    let now = Snapshot::new(Timestamp::now(), document).total;
+    Ok(baseline.map(|baseline| now.saturating_sub(baseline)))
+}
+
+/// Where the history log for `files` lives: `.ncount/history.jsonl` in the
+/// deepest directory holding all of them.
+fn store_path(files: &[PathBuf]) -> PathBuf {
+    common_dir(files.iter().filter_map(|f| f.parent()))
+        .unwrap_or_default()
+        .join(STORE_DIR)
+        .join(STORE_FILE)
+}
+
+/// The deepest directory containing every one of `dirs`.
+fn common_dir<'a>(dirs: impl Iterator<Item = &'a Path>) -> Option<PathBuf> {
+    dirs.fold(None, |common: Option<PathBuf>, dir| match common {
+        None => Some(dir.to_path_buf()),
+        Some(common) => Some(
+            common
+                .components()
+                .zip(dir.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        ),
+    })
+}
+
+/// Finds the history log for `paths` (the same arguments the history was
+/// recorded with): starting from the directory that holds them all — or
+/// the current directory, given none — and walking up until a log turns up.
+fn locate(paths: &[String]) -> Result<PathBuf> {
+    let dirs: Vec<PathBuf> = paths
+        .iter()
+        .map(|candidate| {
+            let p = Path::new(candidate);
+            if p.is_dir() {
+                p.to_path_buf()
+            } else if p.exists() {
+                p.parent().map(Path::to_path_buf).unwrap_or_default()
+            } else {
+                pattern_base_dir(candidate)
+            }
+        })
+        .collect();
+    let start = match common_dir(dirs.iter().map(PathBuf::as_path)) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => PathBuf::from("."),
+    };
+    let start = fs::canonicalize(&start).unwrap_or(start);
+
+    start
+        .ancestors()
+        .map(|dir| dir.join(STORE_DIR).join(STORE_FILE))
+        .find(|store| store.is_file())
+        .ok_or(Error::NoHistory(start))
+}
+
+fn load(path: &Path) -> Result<Vec<Snapshot>> {
+    let text = fs::read_to_string(path)?;
+    let mut snapshots = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str::<Snapshot>(line).map_err(|source| Error::HistoryEntry {
+                path: path.to_path_buf(),
+                line: index + 1,
+                source,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    snapshots.sort_by_key(|s| s.timestamp);
+    Ok(snapshots)
+}
+
+/// One line of a history report: where the words stood at the end of a
+/// period (or for a section, now) and how far they moved.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub label: String,
+    pub words: u32,
+    pub delta: i64,
+}
+
+/// Groups consecutive snapshots under `label` (a day, a week...) and
+/// reports each group's closing total against the total the group started
+/// from: the last snapshot before it, or, for the very first group, its
+/// own first snapshot.
+pub fn by_period(snapshots: &[Snapshot], label: impl Fn(Timestamp) -> String) -> Vec<Change> {
+    let mut changes: Vec<Change> = Vec::new();
+    let mut baseline = snapshots.first().map(|s| s.total).unwrap_or_default();
+    for snapshot in snapshots {
+        let label = label(snapshot.timestamp);
+        match changes.last_mut() {
+            Some(change) if change.label == label => {
+                change.words = snapshot.total;
+            }
+            _ => {
+                if let Some(change) = changes.last() {
+                    baseline = change.words;
+                }
+                changes.push(Change {
+                    label,
+                    words: snapshot.total,
+                    delta: 0,
+                });
+            }
+        }
+        let change = changes.last_mut().unwrap();
+        change.delta = change.words as i64 - baseline as i64;
+    }
+    changes
+}
+
+/// Each section's words now against the first snapshot: sections that
+/// were already there count from their first recorded size, sections
+/// written since count from zero, and sections since removed show what
+/// they lost.
+pub fn by_section(snapshots: &[Snapshot]) -> Vec<Change> {
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        return Vec::new();
+    };
+    let before: HashMap<&str, u32> = section_words(first).into_iter().collect();
+    let now = section_words(last);
+
+    let mut changes: Vec<Change> = now
+        .iter()
+        .map(|&(path, words)| Change {
+            label: path.to_string(),
+            words,
+            delta: words as i64 - before.get(path).copied().unwrap_or_default() as i64,
+        })
+        .collect();
+
+    let mut removed: Vec<&str> = Vec::new();
+    for section in snapshots.iter().flat_map(|s| &s.sections) {
+        let path = section.path.as_str();
+        if !now.iter().any(|&(p, _)| p == path) && !removed.contains(&path) {
+            removed.push(path);
+        }
+    }
+    changes.extend(removed.into_iter().filter_map(|path| {
+        let words = before.get(path).copied().unwrap_or_default();
+        (words > 0).then(|| Change {
+            label: path.to_string(),
+            words: 0,
+            delta: -(words as i64),
+        })
+    }));
+    changes
+}
+
+/// A snapshot's words per heading path, in document order. Paths that
+/// repeat (two identically named sections under one parent) are summed.
+fn section_words(snapshot: &Snapshot) -> Vec<(&str, u32)> {
+    let mut words: Vec<(&str, u32)> = Vec::new();
+    for section in &snapshot.sections {
+        match words.iter_mut().find(|(path, _)| *path == section.path) {
+            Some((_, total)) => *total += section.words,
+            None => words.push((&section.path, section.words)),
+        }
+    }
+    words
+}
+
+pub fn run(args: &HistoryArgs) -> Result<()> {
+    let store = locate(args.paths())?;
+    let snapshots = load(&store)?;
+    if snapshots.is_empty() {
+        return Err(Error::NoHistory(store));
+    }
+
+    let tz = TimeZone::system();
+    let (title, changes) = match args.by() {
+        Grouping::Day => (
+            "Day",
+            by_period(&snapshots, |ts| ts.to_zoned(tz.clone()).date().to_string()),
+        ),
+        Grouping::Week => (
+            "Week",
+            by_period(&snapshots, |ts| {
+                let week = ts.to_zoned(tz.clone()).date().iso_week_date();
+                format!("{}-W{:02}", week.year(), week.week())
+            }),
+        ),
+        Grouping::Section => ("§", by_section(&snapshots)),
+    };
+
+    writeln!(io::stdout().lock(), "{}", build_table(title, &changes))?;
+    Ok(())
+}
+
+/// Same look as `StatFmt`'s table.
+fn build_table(title: &str, changes: &[Change]) -> Table {
+    let mut table = fmt::new_table();
+
+    let row = table.add_empty_row();
+    row.add_cell(Cell::new_align(title, Alignment::LEFT));
+    row.add_cell(Cell::new_align("Words", Alignment::RIGHT));
+    row.add_cell(Cell::new_align("Change", Alignment::RIGHT));
+
+    for change in changes {
+        let row = table.add_empty_row();
+        row.add_cell(Cell::new_align(&change.label, Alignment::LEFT).style_spec("b"));
+        row.add_cell(Cell::new_align(&change.words.to_string(), Alignment::RIGHT));
+        row.add_cell(Cell::new_align(
+            &format!("{:+}", change.delta),
+            Alignment::RIGHT,
+        ));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(at: &str, sections: &[(&str, u32)]) -> Snapshot {
+        let sections: Vec<_> = sections
+            .iter()
+            .map(|(path, words)| SectionTotal {
+                path: path.to_string(),
+                words: *words,
+            })
+            .collect();
+        Snapshot {
+            timestamp: at.parse().unwrap(),
+            total: sections.iter().map(|s| s.words).sum(),
+            sections,
+        }
+    }
+
+    fn by_day(snapshots: &[Snapshot]) -> Vec<Change> {
+        by_period(snapshots, |ts| {
+            ts.to_zoned(TimeZone::UTC).date().to_string()
+        })
+    }
+
+    fn change(label: &str, words: u32, delta: i64) -> Change {
+        Change {
+            label: label.to_string(),
+            words,
+            delta,
+        }
+    }
+
+    #[test]
+    fn snapshot_names_sections_by_path_and_totals_them() {
+        let mut builder = crate::document::DocumentBuilder::new();
+        builder.apply(crate::filter::TextFilter::new().lex("# One\n\na b\n\n## 1.1\n\nc d e"));
+        let snapshot = Snapshot::new(Timestamp::UNIX_EPOCH, &builder.finalize());
+
+        assert_eq!(snapshot.total, 5);
+        let paths: Vec<_> = snapshot.sections.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(paths, ["One", "One > 1.1"]);
+    }
+
+    #[test]
+    fn days_compare_against_the_previous_days_close() {
+        let snapshots = [
+            snapshot("2026-10-01T09:00:00Z", &[("One", 1000)]),
+            snapshot("2026-10-01T17:00:00Z", &[("One", 1400)]),
+            snapshot("2026-10-02T10:00:00Z", &[("One", 1300)]),
+            snapshot("2026-10-04T10:00:00Z", &[("One", 1300), ("Two", 500)]),
+        ];
+        assert_eq!(
+            by_day(&snapshots),
+            [
+                change("2026-10-01", 1400, 400),
+                change("2026-10-02", 1300, -100),
+                change("2026-10-04", 1800, 500),
+            ]
+        );
+    }
+
+    #[test]
+    fn sections_report_growth_new_and_removed() {
+        let snapshots = [
+            snapshot("2026-10-01T09:00:00Z", &[("One", 1000), ("Cut", 300)]),
+            snapshot("2026-10-02T09:00:00Z", &[("One", 1200), ("Two", 400)]),
+        ];
+        assert_eq!(
+            by_section(&snapshots),
+            [
+                change("One", 1200, 200),
+                change("Two", 400, 400),
+                change("Cut", 0, -300),
+            ]
+        );
+    }
+
+    #[test]
+    fn common_dir_finds_the_shared_ancestor() {
+        let dirs = [Path::new("/book/part-1/ch-01"), Path::new("/book/part-2")];
+        assert_eq!(common_dir(dirs.into_iter()), Some(PathBuf::from("/book")));
+    }
+
+    #[test]
+    fn record_then_load_round_trips() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("ch1.md");
+        fs::write(&file, "# One\n\nalpha beta").unwrap();
+        let mut builder = crate::document::DocumentBuilder::new();
+        builder.apply(crate::filter::TextFilter::new().lex("# One\n\nalpha beta"));
+        let document = builder.finalize();
+
+        let store = record(std::slice::from_ref(&file), &document).unwrap();
+        record(&[file], &document).unwrap();
+
+        assert_eq!(store, temp.path().join(".ncount").join("history.jsonl"));
+        let snapshots = load(&store).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[1].total, 2);
+        assert_eq!(
+            locate(&[temp.path().to_string_lossy().into_owned()]).unwrap(),
+            store.canonicalize().unwrap()
+        );
+    }
+}
@@ -1,4 +1,7 @@
 use std::env;
+use std::fmt::Display;
+
+use owo_colors::OwoColorize;
 
 pub fn init() {
     let environment = env::var("RUST_LOG").or_else(|_| env::var("LOG"));
@@ -10,3 +13,17 @@ pub fn init() {
             .init();
     }
 }
+
+/// Something worth telling the user about that doesn't stop the run.
+pub fn warn(message: impl Display) {
+    eprintln!("{}", format!("warning: {message}").yellow());
+}
+
+/// `1 file`, `2 files`
+pub fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
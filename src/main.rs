@@ -1,29 +1,43 @@
 mod cli;
+mod config;
+mod counting;
 mod document;
 mod error;
+mod estimate;
 mod filter;
 mod fmt;
+mod goals;
+mod history;
 mod log;
+mod markup;
+mod matcher;
+mod order;
 mod tui;
 
-use std::{fs, process};
+use std::{
+    io::{self, Read},
+    process,
+};
 
-use cli::{Args, CommonArgs};
+use cli::{Args, Command, CommonArgs};
 use document::DocumentBuilder;
-use filter::TextFilter;
 use fmt::StatFmt;
+use goals::Progress;
 
 type Result<T, E = error::Error> = std::result::Result<T, E>;
 
 fn main() {
     log::init();
 
-    let args = Args::parse();
-    let result = if args.common.watch() {
-        tui::run(&args.common)
-    } else {
-        run_once(&args.common)
-    };
+    let mut args = Args::parse();
+    let result = args
+        .common
+        .load_config()
+        .and_then(|()| match &args.command {
+            Some(Command::History(history)) => history::run(history),
+            None if args.common.watch() => tui::run(&args.common),
+            None => run_once(&args.common),
+        });
 
     if let Err(e) = result {
         eprintln!("{e}");
@@ -32,20 +46,53 @@ fn main() {
 }
 
 fn run_once(args: &CommonArgs) -> Result<()> {
-    let filter = TextFilter::new();
+    let filter = args.text_filter()?;
     let mut builder = DocumentBuilder::new();
 
-    for file in args.materialize_files()? {
+    let files = if args.reads_stdin()? {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        builder.apply(filter.lex(&text));
+        Vec::new()
+    } else {
+        args.materialize_files()?
+    };
+    for file in &files {
         tracing::debug!("path: {}", file.display());
-        let text = fs::read_to_string(file)?;
+        let text = match cli::read_text(file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                log::warn(format_args!("skipped {}: not a text file", file.display()));
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        builder.set_source(file);
         builder.apply(filter.lex(&text))
     }
+    let document = builder.finalize();
 
+    if args.record() {
+        let store = history::record(&files, &document)?;
+        tracing::debug!("recorded snapshot in {}", store.display());
+    }
+
+    let goals = args.goals()?;
     let mut formatter = StatFmt::new(args.verbose());
-    if let Some(filter) = args.filter() {
-        formatter.add_filter(filter);
+    formatter.set_output(args.format());
+    formatter.set_columns(args.columns().to_vec());
+    formatter.set_rates(args.rates());
+    formatter.set_distribution(args.distribution());
+    formatter.set_longest(args.longest());
+    if let Some(daily) = goals.daily {
+        let written = history::written_today(&files, &document)?;
+        formatter.set_daily(written.map(|words| Progress::new(words, daily)));
+    }
+    formatter.set_goals(goals);
+    if let Some(matcher) = args.heading_matcher()? {
+        formatter.add_filter(matcher);
     }
 
-    formatter.format(&builder.finalize())?;
+    formatter.format(&document)?;
     Ok(())
 }
@@ -0,0 +1,351 @@
+//! Inline Markdown that shouldn't count as words: link and image targets,
+//! HTML tags, and emphasis markers.
+
+use std::borrow::Cow;
+
+/// `text` as a reader would see it, more or less: links and images reduced
+/// to their visible text, URLs and HTML tags dropped (a tag leaves a space,
+/// so `one<br>two` is still two words), and `*`, `_` and `~` removed, so
+/// `un*believ*able` is one word. A link reference definition
+/// (`[id]: https://...`) is nothing at all.
+///
+/// Most lines have none of this, and come back as they are.
+pub fn strip(text: &str) -> Cow<'_, str> {
+    strip_wrapped(text, &mut false)
+}
+
+/// `strip` for one line of a paragraph at a time. `open` says whether the
+/// lines before left a link's label open, as a hard wrap does to `[the` /
+/// `door](https://...)`, and is updated for the line after, so the second
+/// line's URL goes the way of a one-line link's.
+pub fn strip_wrapped<'a>(text: &'a str, open: &mut bool) -> Cow<'a, str> {
+    if std::mem::take(open) {
+        let Some(end) = label_end(text) else {
+            *open = true;
+            return strip(text);
+        };
+        let mut out = String::with_capacity(text.len());
+        strip_into(&text[..end], &mut out, &mut false);
+        let after = &text[end + 1..];
+        match target(after) {
+            Some(after) => strip_into(after, &mut out, open),
+            None => {
+                out.push(']');
+                strip_into(after, &mut out, open);
+            }
+        }
+        return Cow::Owned(out);
+    }
+
+    if !text
+        .bytes()
+        .any(|b| matches!(b, b'[' | b'<' | b'*' | b'_' | b'~'))
+    {
+        return Cow::Borrowed(text);
+    }
+    if is_reference_definition(text) {
+        return Cow::Borrowed("");
+    }
+
+    let mut out = String::with_capacity(text.len());
+    strip_into(text, &mut out, open);
+    Cow::Owned(out)
+}
+
+/// Strips `text` into `out`, setting `open` if a `[` is never closed.
+fn strip_into(text: &str, out: &mut String, open: &mut bool) {
+    let mut rest = text;
+    while let Some(i) = rest.find(['[', '<', '*', '_', '~']) {
+        let (before, from) = rest.split_at(i);
+        out.push_str(before);
+        rest = match from.as_bytes()[0] {
+            b'[' => match link(from) {
+                Some((label, after)) => {
+                    // The `!` of an image goes along with its URL.
+                    if before.ends_with('!') {
+                        out.pop();
+                    }
+                    strip_into(label, out, &mut false);
+                    after
+                }
+                None => {
+                    if closing(from, b'[', b']').is_none() {
+                        *open = true;
+                    }
+                    out.push('[');
+                    &from[1..]
+                }
+            },
+            b'<' => match tag(from) {
+                Some(after) => {
+                    out.push(' ');
+                    after
+                }
+                None => {
+                    out.push('<');
+                    &from[1..]
+                }
+            },
+            // Emphasis and strikethrough markers.
+            _ => &from[1..],
+        };
+    }
+    out.push_str(rest);
+}
+
+/// For `[label](url)` or `[label][id]` at the start of `s`, the label and
+/// whatever follows the link.
+fn link(s: &str) -> Option<(&str, &str)> {
+    let end = closing(s, b'[', b']')?;
+    let (label, after) = (&s[1..end], &s[end + 1..]);
+    Some((label, target(after)?))
+}
+
+/// For a link's `(url)` or `[id]` at the start of `s`, whatever follows it.
+fn target(s: &str) -> Option<&str> {
+    let close = match s.as_bytes().first() {
+        Some(b'(') => closing(s, b'(', b')')?,
+        Some(b'[') => closing(s, b'[', b']')?,
+        _ => return None,
+    };
+    Some(&s[close + 1..])
+}
+
+/// The index of the `]` that closes a label opened on an earlier line.
+fn label_end(s: &str) -> Option<usize> {
+    let mut depth = 1;
+    for (i, b) in s.bytes().enumerate() {
+        if b == b'[' {
+            depth += 1;
+        } else if b == b']' {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// The index of the bracket that closes the one `s` starts with.
+fn closing(s: &str, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    for (i, b) in s.bytes().enumerate() {
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// HTML elements a manuscript might plausibly use. Anything else between
+/// angle brackets (`x <y and y> z`) is more likely prose than a tag.
+const ELEMENTS: &str = "\
+    a abbr aside b bdi bdo blockquote br center cite code dd del details \
+    dfn div dl dt em figcaption figure font h1 h2 h3 h4 h5 h6 hr i img ins \
+    kbd li mark ol p pre q rp rt ruby s samp section small span strike \
+    strong sub summary sup table tbody td th thead tr tt u ul var wbr";
+
+/// For an HTML tag (`<em>`, `<span class="x">`, `</span>`, `<br/>`,
+/// `<!DOCTYPE ...>`) or an autolink (`<https://...>`, `<me@example.com>`)
+/// at the start of `s`, whatever follows it. A tag has to be well formed:
+/// a known element (or a custom one, with a `-` in its name) right after
+/// the `<`, then attributes, then `>`.
+fn tag(s: &str) -> Option<&str> {
+    let rest = &s[1..];
+    if let Some(body) = rest.strip_prefix('!') {
+        if !body.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        return Some(&body[body.find('>')? + 1..]);
+    }
+    if let Some(after) = autolink(rest) {
+        return Some(after);
+    }
+
+    let (closing, rest) = match rest.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let name_len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(rest.len());
+    let name = &rest[..name_len];
+    let known = name.contains('-')
+        || ELEMENTS
+            .split_whitespace()
+            .any(|element| element.eq_ignore_ascii_case(name));
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) || !known {
+        return None;
+    }
+
+    let mut rest = &rest[name_len..];
+    if !closing {
+        rest = attributes(rest)?;
+    }
+    let rest = rest.trim_start();
+    let rest = if closing {
+        rest
+    } else {
+        rest.strip_prefix('/').unwrap_or(rest)
+    };
+    rest.strip_prefix('>')
+}
+
+/// Skips a tag's attributes (`class="x"`, `hidden`), each after some
+/// whitespace, returning what follows them.
+fn attributes(mut s: &str) -> Option<&str> {
+    loop {
+        let trimmed = s.trim_start();
+        let name_len = trimmed
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-')))
+            .unwrap_or(trimmed.len());
+        if trimmed.len() == s.len()
+            || !trimmed.starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '_' | ':'))
+        {
+            return Some(s);
+        }
+        s = &trimmed[name_len..];
+        if let Some(value) = s.trim_start().strip_prefix('=') {
+            s = attribute_value(value.trim_start())?;
+        }
+    }
+}
+
+/// Skips a quoted or unquoted attribute value.
+fn attribute_value(s: &str) -> Option<&str> {
+    if let Some(quote) = s.chars().next().filter(|&c| c == '"' || c == '\'') {
+        let end = s[1..].find(quote)?;
+        return Some(&s[end + 2..]);
+    }
+    let end = s
+        .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`'))
+        .unwrap_or(s.len());
+    (end > 0).then(|| &s[end..])
+}
+
+/// For an autolink's body (`https://...>` or `me@example.com>`), whatever
+/// follows it: a URL scheme or an `@`, and no spaces.
+fn autolink(s: &str) -> Option<&str> {
+    let end = s.find('>')?;
+    let body = &s[..end];
+    if body.is_empty() || body.contains(|c: char| c.is_whitespace() || c == '<') {
+        return None;
+    }
+    let scheme = body.split(':').next().unwrap_or_default();
+    let is_url = body.contains(':')
+        && (2..=32).contains(&scheme.len())
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'));
+    let is_email = body.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty() && domain.contains('.') && !domain.starts_with('.')
+    });
+    (is_url || is_email).then(|| &s[end + 1..])
+}
+
+/// `[id]: url`, with at most three spaces of indent. Footnotes (`[^1]: `)
+/// are a noise rule's business.
+fn is_reference_definition(line: &str) -> bool {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 || rest.starts_with("[^") {
+        return false;
+    }
+    rest.starts_with('[')
+        && closing(rest, b'[', b']').is_some_and(|end| end > 1 && rest[end + 1..].starts_with(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_is_borrowed() {
+        assert!(matches!(strip("Nothing to see here."), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn links_and_images_keep_only_their_text() {
+        assert_eq!(
+            strip("Open [the door](https://example.com/a-very-long-url) now"),
+            "Open the door now"
+        );
+        assert_eq!(
+            strip("![a red door](img/door_(red).png \"Door\") and [it][door]."),
+            "a red door and it."
+        );
+        assert_eq!(
+            strip("[a [nested] label](x) [sic] [unclosed"),
+            "a [nested] label [sic] [unclosed"
+        );
+    }
+
+    #[test]
+    fn tags_and_autolinks_are_dropped() {
+        assert_eq!(
+            strip("one<br>two <span class=\"x\">three</span> <https://example.com> 1 < 2"),
+            "one two  three    1 < 2"
+        );
+    }
+
+    #[test]
+    fn only_well_formed_tags_are_dropped() {
+        assert_eq!(strip("x <y and y> z"), "x <y and y> z");
+        assert_eq!(strip("a < b and c > d"), "a < b and c > d");
+        assert_eq!(strip("<em >so</em > <br/>"), " so   ");
+        assert_eq!(
+            strip("<img src=door.png alt='a door' hidden><my-aside>x</my-aside>"),
+            "  x "
+        );
+        assert_eq!(
+            strip("<span class=\"x>unclosed"),
+            "<span class=\"x>unclosed"
+        );
+        assert_eq!(
+            strip("<b>bold</b>, <note to self>"),
+            " bold , <note to self>"
+        );
+    }
+
+    #[test]
+    fn links_can_wrap_across_lines() {
+        let mut open = false;
+        assert_eq!(strip_wrapped("He opened [the", &mut open), "He opened [the");
+        assert!(open);
+        assert_eq!(strip_wrapped("big red", &mut open), "big red");
+        assert!(open);
+        assert_eq!(
+            strip_wrapped("door](https://example.com/a/b) *slowly*.", &mut open),
+            "door slowly."
+        );
+        assert!(!open);
+
+        let mut open = true;
+        assert_eq!(strip_wrapped("sic] and [more", &mut open), "sic] and [more");
+        assert!(open);
+    }
+
+    #[test]
+    fn emphasis_markers_are_dropped() {
+        assert_eq!(
+            strip("*so* __very__ un*believ*able ~~struck~~"),
+            "so very unbelievable struck"
+        );
+    }
+
+    #[test]
+    fn reference_definitions_are_nothing() {
+        assert_eq!(strip("[door]: https://example.com/door \"Door\""), "");
+        assert_eq!(
+            strip("    [door]: https://example.com"),
+            "    [door]: https://example.com"
+        );
+        assert_eq!(strip("[^1]: a footnote"), "[^1]: a footnote");
+    }
+}
@@ -0,0 +1,236 @@
+use caseless::default_case_fold_str;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use crate::Result;
+use crate::error::Error;
+
+/// How `--filter` picks out headings. Every mode ignores case, Unicode
+/// case included. Prefix, substring and fuzzy fold it fully, so `straße`
+/// finds "STRASSE"; a regex compares letter by letter, so `élan` finds
+/// "ÉLAN" but `straße` doesn't find "STRASSE".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// headings that start with the filter
+    #[default]
+    Prefix,
+    /// headings that contain the filter anywhere
+    Substring,
+    /// headings that contain the filter's letters in order, not
+    /// necessarily together: `bkshd` finds "Book of Shadows"
+    Fuzzy,
+    /// headings the filter, as a regular expression, matches
+    Regex,
+}
+
+/// A `--filter`, ready to test headings against.
+///
+/// A filter can be a path, with `/` or `>` between headings (`Chapter
+/// II/2.3`, `Part One > *`), to pick out a section by its ancestors as well
+/// as its own heading; `*` stands for any heading at all. The filter is
+/// first tried whole, though, so `Either/Or` still finds a heading of that
+/// name; `\/` and `\>` force a literal `/` and `>`. A regex is never split.
+#[derive(Clone, Debug)]
+pub struct Matcher {
+    filter: String,
+    /// The filter as one heading.
+    whole: Kind,
+    /// One part per heading, outermost first; `None` for `*`. Only for a
+    /// filter that reads differently as a path.
+    path: Option<Vec<Option<Kind>>>,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Prefix(String),
+    Substring(String),
+    Fuzzy(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(mode: MatchMode, filter: &str) -> Result<Self> {
+        let parts = match mode {
+            MatchMode::Regex => Vec::new(),
+            _ => split_path(filter),
+        };
+        let path = if parts.len() > 1 || parts.first().is_some_and(|part| part != filter) {
+            let path = parts
+                .iter()
+                .map(|part| match part.as_str() {
+                    "*" => Ok(None),
+                    part => Kind::new(mode, part).map(Some),
+                })
+                .collect::<Result<_>>()?;
+            Some(path)
+        } else {
+            None
+        };
+        Ok(Self {
+            filter: filter.to_string(),
+            whole: Kind::new(mode, filter)?,
+            path,
+        })
+    }
+
+    /// The filter as given.
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Whether the filter picks out a section, given the headings from the
+    /// top of its document down to its own: either its own heading matches
+    /// the whole filter, or, for a path, its own heading matches the last
+    /// part, its parent's the part before that, and so on up.
+    pub fn is_match(&self, headings: &[&str]) -> bool {
+        if headings
+            .last()
+            .is_some_and(|heading| self.whole.is_match(heading))
+        {
+            return true;
+        }
+        self.path.as_ref().is_some_and(|path| {
+            headings.len() >= path.len()
+                && path
+                    .iter()
+                    .rev()
+                    .zip(headings.iter().rev())
+                    .all(|(part, heading)| part.as_ref().is_none_or(|kind| kind.is_match(heading)))
+        })
+    }
+}
+
+impl Kind {
+    fn new(mode: MatchMode, filter: &str) -> Result<Self> {
+        Ok(match mode {
+            MatchMode::Prefix => Kind::Prefix(default_case_fold_str(filter)),
+            MatchMode::Substring => Kind::Substring(default_case_fold_str(filter)),
+            MatchMode::Fuzzy => Kind::Fuzzy(
+                default_case_fold_str(filter)
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect(),
+            ),
+            MatchMode::Regex => Kind::Regex(
+                RegexBuilder::new(filter)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|source| Error::HeadingPattern {
+                        pattern: filter.to_string(),
+                        source,
+                    })?,
+            ),
+        })
+    }
+
+    fn is_match(&self, heading: &str) -> bool {
+        match self {
+            Kind::Prefix(prefix) => default_case_fold_str(heading).starts_with(prefix.as_str()),
+            Kind::Substring(part) => default_case_fold_str(heading).contains(part.as_str()),
+            Kind::Fuzzy(letters) => {
+                let heading = default_case_fold_str(heading);
+                let mut rest = heading.chars();
+                letters.chars().all(|c| rest.any(|h| h == c))
+            }
+            Kind::Regex(regex) => regex.is_match(heading),
+        }
+    }
+}
+
+/// `filter`'s parts, split at each unescaped `/` or `>` and trimmed.
+fn split_path(filter: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut chars = filter.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('/' | '>')) => part.push(c),
+                Some(c) => {
+                    part.push('\\');
+                    part.push(c);
+                }
+                None => part.push('\\'),
+            },
+            '/' | '>' => parts.push(std::mem::take(&mut part)),
+            c => part.push(c),
+        }
+    }
+    parts.push(part);
+    parts
+        .into_iter()
+        .map(|part| part.trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finds(mode: MatchMode, filter: &str, heading: &str) -> bool {
+        Matcher::new(mode, filter).unwrap().is_match(&[heading])
+    }
+
+    #[test]
+    fn modes_find_more_and_more() {
+        let heading = "From the Book of Shadows";
+        assert!(finds(MatchMode::Prefix, "from the", heading));
+        assert!(!finds(MatchMode::Prefix, "shadows", heading));
+        assert!(finds(MatchMode::Substring, "SHADOWS", heading));
+        assert!(!finds(MatchMode::Substring, "bkshd", heading));
+        assert!(finds(MatchMode::Fuzzy, "bk shd", heading));
+        assert!(!finds(MatchMode::Fuzzy, "shdbk", heading));
+        assert!(finds(MatchMode::Regex, r"book of \w+$", heading));
+        assert!(!finds(MatchMode::Regex, r"^book", heading));
+    }
+
+    #[test]
+    fn case_is_folded_beyond_ascii() {
+        assert!(finds(MatchMode::Prefix, "straße", "STRASSE 9"));
+        assert!(finds(MatchMode::Substring, "σοφία", "ΣΟΦΊΑ"));
+        assert!(finds(MatchMode::Regex, "élan", "ÉLAN VITAL"));
+        assert!(!finds(MatchMode::Regex, "straße", "STRASSE 9"));
+    }
+
+    #[test]
+    fn paths_match_a_heading_and_its_ancestors() {
+        let matcher = Matcher::new(MatchMode::Prefix, "Chapter II/2.3").unwrap();
+        assert!(matcher.is_match(&["Part One", "Chapter II", "2.3"]));
+        assert!(!matcher.is_match(&["Chapter I", "2.3"]));
+        assert!(!matcher.is_match(&["Chapter II", "2.3", "Notes"]));
+        assert!(!matcher.is_match(&["2.3"]));
+
+        let matcher = Matcher::new(MatchMode::Substring, "part one > *").unwrap();
+        assert!(matcher.is_match(&["Part One", "Chapter I"]));
+        assert!(!matcher.is_match(&["Part One"]));
+        assert!(!matcher.is_match(&["Part Two", "Chapter V"]));
+    }
+
+    #[test]
+    fn headings_with_separators_match_whole() {
+        let matcher = Matcher::new(MatchMode::Prefix, "Either/Or").unwrap();
+        assert!(matcher.is_match(&["Part One", "Either/Or"]));
+        assert!(matcher.is_match(&["Either", "Or"]));
+
+        let matcher = Matcher::new(MatchMode::Substring, "pros > cons").unwrap();
+        assert!(matcher.is_match(&["Pros > Cons"]));
+        assert!(!matcher.is_match(&["Cons"]));
+
+        let matcher = Matcher::new(MatchMode::Regex, "^a/b$").unwrap();
+        assert!(matcher.is_match(&["A/B"]));
+        assert!(!matcher.is_match(&["A", "B"]));
+    }
+
+    #[test]
+    fn separators_can_be_escaped() {
+        assert_eq!(split_path(r"Either\/Or > \d+"), [r"Either/Or", r"\d+"]);
+        assert!(finds(MatchMode::Regex, r"^either\/or$", "Either/Or"));
+    }
+
+    #[test]
+    fn bad_regexes_are_reported() {
+        let err = Matcher::new(MatchMode::Regex, "(unclosed").unwrap_err();
+        assert!(matches!(err, Error::HeadingPattern { pattern, .. } if pattern == "(unclosed"));
+    }
+}
@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::Result;
+use crate::log;
+
+/// The order files are counted in, and so the order their sections appear
+/// in (and accumulate the running total in).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    /// sort all files by path, numbers by value (`ch-2` before `ch-10`)
+    #[default]
+    Name,
+    /// keep the paths in the order given (sorting within each)
+    Listed,
+}
+
+/// An `Order`, plus the manifest, if any, that overrides it: files the
+/// manifest lists come first, in its order, and any others after them.
+#[derive(Clone, Debug, Default)]
+pub struct FileOrder {
+    pub order: Order,
+    pub manifest: Option<Manifest>,
+}
+
+impl FileOrder {
+    /// Sorts `items` by `key`: the index of the command-line candidate an
+    /// item came from (its rank) and its (canonical) path. Only
+    /// `Order::Listed` looks at rank.
+    pub fn sort<T>(&self, items: &mut [T], key: impl Fn(&T) -> (usize, &Path)) {
+        let positions = self
+            .manifest
+            .as_ref()
+            .map(Manifest::positions)
+            .unwrap_or_default();
+        let position = |path: &Path| positions.get(path).copied().unwrap_or(usize::MAX);
+        items.sort_by(|a, b| {
+            let (a_rank, a) = key(a);
+            let (b_rank, b) = key(b);
+            position(a)
+                .cmp(&position(b))
+                .then_with(|| match self.order {
+                    Order::Name => natural_cmp(a, b),
+                    Order::Listed => a_rank.cmp(&b_rank).then_with(|| natural_cmp(a, b)),
+                })
+        });
+    }
+}
+
+/// Compares paths the way people number chapters: runs of digits by their
+/// value, so `chapter-2.md` sorts before `chapter-10.md`.
+pub fn natural_cmp(a: &Path, b: &Path) -> Ordering {
+    let (a, b) = (a.to_string_lossy(), b.to_string_lossy());
+    let (mut a_rest, mut b_rest) = (a.as_ref(), b.as_ref());
+    loop {
+        match (a_rest.chars().next(), b_rest.chars().next()) {
+            (None, None) => return a.cmp(&b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, x_tail) = split_digits(a_rest);
+                let (y, y_tail) = split_digits(b_rest);
+                let (x_value, y_value) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let by_value = x_value
+                    .len()
+                    .cmp(&y_value.len())
+                    .then_with(|| x_value.cmp(y_value));
+                if by_value != Ordering::Equal {
+                    return by_value;
+                }
+                (a_rest, b_rest) = (x_tail, y_tail);
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                (a_rest, b_rest) = (&a_rest[x.len_utf8()..], &b_rest[y.len_utf8()..]);
+            }
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// A reading order, as listed in one of:
+///
+/// - an mdBook `SUMMARY.md` (or any Markdown file): the targets of its
+///   links, top to bottom
+/// - a Pandoc defaults or metadata file (`.yaml`/`.yml`): its
+///   `input-files` list
+/// - anything else, e.g. `order.txt`: one path per line, skipping blank
+///   lines and `#` comments
+///
+/// Paths are relative to the manifest itself.
+#[derive(Clone, Debug, Default)]
+pub struct Manifest {
+    /// The manifest's own (canonical) path.
+    path: PathBuf,
+    /// Canonical paths of the listed files that exist.
+    files: Vec<PathBuf>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let entries = match extension.as_deref() {
+            Some("md" | "markdown") => markdown_links(&text),
+            Some("yaml" | "yml") => yaml_input_files(&text),
+            _ => plain_lines(&text),
+        };
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut files = Vec::new();
+        for entry in entries {
+            match fs::canonicalize(dir.join(&entry)) {
+                Ok(file) => files.push(file),
+                Err(_) => log::warn(format_args!(
+                    "{}: no such file {entry:?}; leaving it out",
+                    path.display()
+                )),
+            }
+        }
+        let path = fs::canonicalize(path)?;
+        Ok(Self { path, files })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Where each listed file comes in the manifest, for looking up many
+    /// at once. A file listed twice goes where it first appears.
+    fn positions(&self) -> HashMap<&Path, usize> {
+        let mut positions = HashMap::with_capacity(self.files.len());
+        for (i, file) in self.files.iter().enumerate() {
+            positions.entry(file.as_path()).or_insert(i);
+        }
+        positions
+    }
+}
+
+/// Link targets, skipping URLs, anchors, and mdBook's empty draft links.
+fn markdown_links(text: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    for line in text.lines() {
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            rest = &rest[start + 2..];
+            let Some(end) = rest.find(')') else { break };
+            let target = rest[..end].split('#').next().unwrap_or_default().trim();
+            if !target.is_empty() && !target.contains("://") {
+                links.push(target.replace("%20", " "));
+            }
+            rest = &rest[end..];
+        }
+    }
+    links
+}
+
+/// The `input-files` list (or lone `input-file`) of a Pandoc YAML file.
+/// Just enough YAML for that: a top-level key followed by `- item` lines.
+fn yaml_input_files(text: &str) -> Vec<String> {
+    let unquote = |s: &str| s.trim().trim_matches(['"', '\'']).to_string();
+    let mut files = Vec::new();
+    let mut in_list = false;
+    for line in text.lines() {
+        if let Some(item) = line.trim_start().strip_prefix("- ")
+            && in_list
+        {
+            files.push(unquote(item));
+        } else if let Some(file) = line.strip_prefix("input-file:") {
+            files.push(unquote(file));
+            in_list = false;
+        } else if !line.trim().is_empty() {
+            in_list = line.trim_end() == "input-files:";
+        }
+    }
+    files
+}
+
+fn plain_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        let mut paths: Vec<PathBuf> =
+            ["ch-10.md", "ch-2.md", "ch-1.md", "appendix.md", "ch-02b.md"]
+                .iter()
+                .map(PathBuf::from)
+                .collect();
+        paths.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            paths,
+            ["appendix.md", "ch-1.md", "ch-2.md", "ch-02b.md", "ch-10.md"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn manifests_list_files_in_reading_order() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        for name in ["intro.md", "ch 1.md", "ch2.md"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let expected = ["intro.md", "ch 1.md", "ch2.md"].map(|name| dir.join(name));
+
+        let manifests = [
+            (
+                "SUMMARY.md",
+                "# Summary\n\n[Intro](intro.md)\n\n- [One](ch%201.md#start)\n  - [Two](./ch2.md)\n- [Draft]()\n- [Site](https://example.com)\n",
+            ),
+            (
+                "metadata.yaml",
+                "title: Book\ninput-files:\n  - intro.md\n  - \"ch 1.md\"\n  - 'ch2.md'\ntoc: true\n",
+            ),
+            (
+                "order.txt",
+                "# reading order\nintro.md\n\nch 1.md\nch2.md\n",
+            ),
+        ];
+        for (name, text) in manifests {
+            let path = dir.join(name);
+            fs::write(&path, text).unwrap();
+            assert_eq!(Manifest::load(&path).unwrap().files(), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn manifest_files_come_first_and_the_rest_follow() {
+        let manifest = Manifest {
+            path: PathBuf::from("/order.txt"),
+            files: vec![PathBuf::from("/b/z.md"), PathBuf::from("/a/y.md")],
+        };
+        let order = FileOrder {
+            order: Order::Name,
+            manifest: Some(manifest),
+        };
+        let mut paths = ["/a/x10.md", "/a/y.md", "/a/x9.md", "/b/z.md"].map(PathBuf::from);
+        order.sort(&mut paths, |path| (0, path));
+        assert_eq!(
+            paths,
+            ["/b/z.md", "/a/y.md", "/a/x9.md", "/a/x10.md"].map(PathBuf::from)
+        );
+    }
+}
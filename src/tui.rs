@@ -1,8 +1,12 @@
 mod app;
 mod render;
+mod session;
 mod watch;
 
+use std::env;
 use std::io::{self, Stdout};
+use std::path::Path;
+use std::process::Command;
 use std::time::Duration;
 
 use crossterm::event::{
@@ -31,9 +35,10 @@ pub fn run(common: &CommonArgs) -> Result<()> {
     let dirs = app.pattern_dirs();
     let mut watcher = Watch::new(
         paths.iter().map(|p| p.as_path()),
-        dirs.iter().map(|p| p.as_path()),
+        dirs.iter().map(|(p, recursive)| (p.as_path(), *recursive)),
     )?;
 
+    install_panic_hook();
     let mut terminal = init_terminal()?;
     let outcome = event_loop(&mut terminal, &mut app, &mut watcher);
     restore_terminal()?;
@@ -45,7 +50,6 @@ fn init_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     io::stdout()
         .execute(EnterAlternateScreen)?
         .execute(EnableMouseCapture)?;
-    install_panic_hook();
     Ok(Terminal::new(CrosstermBackend::new(io::stdout()))?)
 }
 
@@ -95,6 +99,10 @@ fn event_loop(
             }
         }
 
+        if let Some((path, line)) = app.edit_request.take() {
+            edit(terminal, app, &path, line)?;
+        }
+
         for path in watcher.changed() {
             app.reload(&path);
         }
@@ -108,7 +116,57 @@ fn event_loop(
     }
 }
 
+/// Hands the terminal over to the editor, opened at `line` of `path`, and
+/// takes it back once the editor exits, reloading the file whether or not
+/// it was saved (the watcher will report it too; reloading twice is cheap).
+fn edit(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    path: &Path,
+    line: u32,
+) -> Result<()> {
+    let editor = editor();
+    restore_terminal()?;
+    let status = editor_command(&editor, path, line).status();
+    *terminal = init_terminal()?;
+
+    match status {
+        Ok(status) if !status.success() => {
+            app.notice = Some(format!("editor exited with {status}"));
+        }
+        Ok(_) => {}
+        Err(e) => app.notice = Some(format!("couldn't start {editor:?}: {e}")),
+    }
+    app.reload(path);
+    Ok(())
+}
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`, which is nearly always there.
+fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"))
+}
+
+/// `editor +line path`, run by the shell the way git runs `$EDITOR`, so
+/// the editor may come with arguments of its own (`EDITOR="emacs -nw"`) and
+/// quotes (`"/Applications/Sublime Text.app/.../subl" -w`). The line and
+/// path are passed as the script's own arguments, never spliced into it.
+fn editor_command(editor: &str, path: &Path, line: u32) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg("sh")
+        .arg(format!("+{line}"))
+        .arg(path);
+    command
+}
+
 fn handle_key(app: &mut App, key: KeyEvent, rows: &[RowData], page_size: usize) {
+    app.notice = None;
     if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
         app.should_quit = true;
         return;
@@ -202,6 +260,9 @@ fn handle_normal_key(app: &mut App, key: KeyEvent, rows: &[RowData], page_size:
         KeyCode::Left | KeyCode::Char('h') => app.collapse_selected(rows),
         KeyCode::Char('f') | KeyCode::Char('/') => app.enter_filter_mode(),
         KeyCode::Char('?') => app.mode = Mode::Help,
+        KeyCode::Char('e') => app.edit_selected(rows),
+        KeyCode::Char('s') => app.cycle_sort(rows),
+        KeyCode::Char('S') => app.toggle_sort_direction(rows),
         _ => {}
     }
 }
@@ -227,6 +288,7 @@ fn handle_filter_key(app: &mut App, key: KeyEvent) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
 
     fn table(y: u16, height: u16) -> Rect {
         Rect::new(0, y, 80, height)
@@ -263,6 +325,45 @@ mod tests {
         assert_eq!(clicked_row(4, table(0, 10), 0, 3), None);
     }
 
+    #[test]
+    fn editor_arguments_come_before_the_line_and_file() {
+        let command = editor_command("emacs -nw", Path::new("book/one.md"), 12);
+        assert_eq!(command.get_program(), "sh");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["-c", "emacs -nw \"$@\"", "sh", "+12", "book/one.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn editor_paths_can_be_quoted() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("Sublime Text.app");
+        fs::create_dir(&bin).unwrap();
+        let editor = bin.join("subl");
+        let out = dir.path().join("args");
+        fs::write(
+            &editor,
+            format!("#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}'\n", out.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let status = editor_command(
+            &format!("\"{}\" -w", editor.display()),
+            Path::new("my book/one.md"),
+            12,
+        )
+        .status()
+        .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            fs::read_to_string(out).unwrap(),
+            "-w\n+12\nmy book/one.md\n"
+        );
+    }
+
     #[test]
     fn clicked_row_respects_a_nonzero_origin() {
         // A table not anchored at row 0: header at y=2, data from y=3,
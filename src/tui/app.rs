@@ -1,15 +1,25 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use ratatui::widgets::TableState;
 
 use crate::Result;
-use crate::cli::{CommonArgs, WatchSource, expand_pattern, pattern_base_dir};
-use crate::document::{Document, DocumentBuilder, Paragraphs};
+use crate::cli::{
+    CommonArgs, Walk, WatchSource, expand_pattern, pattern_base_dir, pattern_depth, read_text,
+};
+use crate::document::{Document, DocumentBuilder, Location, Paragraphs};
+use crate::estimate::Rates;
 use crate::filter::TextFilter;
+use crate::fmt::Column;
+use crate::goals::Goals;
+use crate::log;
+use crate::matcher::{MatchMode, Matcher};
+use crate::order::FileOrder;
+
+use super::session::Session;
 
 pub struct LoadedFile {
     pub path: PathBuf,
@@ -22,6 +32,59 @@ pub struct LoadedFile {
     /// (`App::sync_patterns`); literal-arg files stay forever and merely
     /// hide when unreadable.
     pub from_pattern: bool,
+    /// Index of the command-line path this file came from, for
+    /// `Order::Listed`.
+    pub rank: usize,
+}
+
+/// How a section changed in the latest reload of its file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// Its paragraphs changed, adding this many words (or cutting them).
+    Words(i64),
+    /// It wasn't there before.
+    Added,
+}
+
+/// How long a reload's changes stay highlighted.
+const HIGHLIGHT_FOR: Duration = Duration::from_secs(3);
+
+/// How sibling sections are ordered in the table. A section's subtree
+/// moves with it, so sorting never breaks up the tree folding works on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sort {
+    #[default]
+    Document,
+    Words,
+    Paragraphs,
+    AverageLength,
+    Longest,
+}
+
+impl Sort {
+    fn next(self) -> Self {
+        match self {
+            Sort::Document => Sort::Words,
+            Sort::Words => Sort::Paragraphs,
+            Sort::Paragraphs => Sort::AverageLength,
+            Sort::AverageLength => Sort::Longest,
+            Sort::Longest => Sort::Document,
+        }
+    }
+
+    /// What a section is ranked by: its whole subtree, so a chapter ranks
+    /// the same folded or not.
+    fn key(self, row: &RowData) -> u32 {
+        let p = &row.subtree_paragraphs;
+        match self {
+            Sort::Document => 0,
+            Sort::Words => p.total,
+            Sort::Paragraphs => p.count,
+            Sort::AverageLength if p.is_zero() => 0,
+            Sort::AverageLength => p.average_len(),
+            Sort::Longest => p.max,
+        }
+    }
 }
 
 pub enum Mode {
@@ -34,14 +97,24 @@ pub enum Mode {
     Help,
 }
 
+/// The headings from the top of a file down to a section's own, each with
+/// how many siblings of the same name come before it: `[("Part One", 0),
+/// ("Interlude", 1)]` is the second "Interlude" in "Part One".
+pub type SectionPath = Vec<(String, usize)>;
+
 /// A single document section, retaining enough tree metadata for folding.
 #[derive(Clone)]
 pub struct RowData {
     pub path: PathBuf,
     pub heading: String,
     pub level: i32,
+    /// Where the heading is in `path`.
+    pub location: Location,
     pub paragraphs: Paragraphs,
     pub parent: Option<(PathBuf, String)>,
+    /// Where the section sits in its file, which, unlike its heading, tells
+    /// repeated headings apart.
+    pub position: SectionPath,
     /// `Paragraphs` aggregated over this section plus every descendant. Used
     /// for the detail columns when a parent is collapsed (its children are
     /// hidden, so the row speaks for the whole subtree); a leaf's value
@@ -74,55 +147,95 @@ pub struct App {
     /// changed shape — an index alone is ambiguous once rows shift.
     last_rows_keys: Vec<(PathBuf, String)>,
     pub status: Option<String>,
+    /// A one-off message for the footer (an editor that wouldn't start),
+    /// shown until the next key press.
+    pub notice: Option<String>,
     pub should_quit: bool,
+    /// A file and line to open in the editor, for the event loop, which
+    /// owns the terminal, to act on.
+    pub edit_request: Option<(PathBuf, u32)>,
+    pub goals: Goals,
+    /// Extra stats columns, shown alongside Count/Avg/Long.
+    pub columns: Vec<Column>,
+    pub rates: Rates,
+    /// The words as they stood at startup, for the session's progress.
+    pub session: Session,
+    pub sort: Sort,
+    /// Largest first, unless toggled; `Sort::Document` ignores it.
+    pub descending: bool,
+    /// What the latest reloads changed, and when, keyed by file and
+    /// position (like the session's baseline), so repeated headings each
+    /// get their own.
+    changes: HashMap<(PathBuf, SectionPath), (Change, Instant)>,
     /// Live glob patterns from the command line (see
-    /// `CommonArgs::watch_sources`), re-expanded by `sync_patterns`.
-    patterns: Vec<String>,
+    /// `CommonArgs::watch_sources`), re-expanded by `sync_patterns`, each
+    /// with its rank.
+    patterns: Vec<(usize, String)>,
+    walk: Walk,
+    order: FileOrder,
     text_filter: TextFilter,
+    /// How `filter` matches headings.
+    match_mode: MatchMode,
+    /// `filter` compiled (or why it wouldn't), along with the filter it was
+    /// compiled from, so a regex is built once rather than on every frame.
+    matcher: Option<(String, std::result::Result<Matcher, String>)>,
 }
 
 impl App {
     pub fn load(common: &CommonArgs) -> Result<Self> {
-        let text_filter = TextFilter::new();
+        // Never true in watch mode, but rejects `-w -`.
+        common.reads_stdin()?;
+        let text_filter = common.text_filter()?;
+        let walk = common.walk()?;
         let mut files = Vec::new();
         let mut patterns = Vec::new();
         // Strict on purpose: at startup every resolved path must read
         // cleanly on the first try — no retries, no hiding. (Contrast
         // `reload`, where a vanished file is a normal event, not an error.)
-        for source in common.watch_sources()? {
+        // The one exception is a file that isn't text at all, which is
+        // left out, same as in run-once mode.
+        for (rank, source) in common.watch_sources()?.into_iter().enumerate() {
             match source {
                 WatchSource::Literal(paths) => {
                     for path in paths {
-                        let text = fs::read_to_string(&path)?;
+                        let Some(text) = read_startup(&path)? else {
+                            continue;
+                        };
                         let document = build_document(&text_filter, &text);
                         files.push(LoadedFile {
                             path,
                             document: Some(document),
                             from_pattern: false,
+                            rank,
                         });
                     }
                 }
                 WatchSource::Pattern(pattern) => {
-                    for path in expand_pattern(&pattern) {
-                        let text = fs::read_to_string(&path)?;
+                    for path in expand_pattern(&pattern, &walk) {
+                        let Some(text) = read_startup(&path)? else {
+                            continue;
+                        };
                         let document = build_document(&text_filter, &text);
                         files.push(LoadedFile {
                             path,
                             document: Some(document),
                             from_pattern: true,
+                            rank,
                         });
                     }
-                    patterns.push(pattern);
+                    patterns.push((rank, pattern));
                 }
             }
         }
-        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let order = common.file_order();
+        order.sort(&mut files, |f| (f.rank, &f.path));
 
         let mut table_state = TableState::default();
         if !files.is_empty() {
             table_state.select(Some(0));
         }
 
+        let session = Session::start(&files);
         Ok(Self {
             files,
             filter: common.filter().map(String::from),
@@ -133,9 +246,22 @@ impl App {
             deferred_selection: None,
             last_rows_keys: Vec::new(),
             status: None,
+            notice: None,
             should_quit: false,
+            edit_request: None,
+            goals: common.goals()?,
+            columns: common.columns().to_vec(),
+            rates: common.rates(),
+            session,
+            sort: Sort::Document,
+            descending: true,
+            changes: HashMap::new(),
             patterns,
+            walk,
+            order,
             text_filter,
+            match_mode: common.match_mode(),
+            matcher: None,
         })
     }
 
@@ -144,16 +270,19 @@ impl App {
     }
 
     /// Directories to watch beyond the parents of the current files: the
-    /// literal prefix of each live pattern. Without this, a pattern that
-    /// matches nothing at startup watches nothing, and files matching it
-    /// later would appear (via `sync_patterns`) but never live-refresh
-    /// their content. Prefixes that don't exist yet can't be watched and
-    /// are skipped.
-    pub fn pattern_dirs(&self) -> Vec<PathBuf> {
+    /// literal prefix of each live pattern, and whether to watch below it
+    /// (for patterns that reach further down, see `pattern_depth`). Without this, a pattern that matches
+    /// nothing at startup watches nothing, and files matching it later
+    /// would appear (via `sync_patterns`) but never live-refresh their
+    /// content. Prefixes that don't exist yet can't be watched and are
+    /// skipped.
+    pub fn pattern_dirs(&self) -> Vec<(PathBuf, bool)> {
         self.patterns
             .iter()
-            .map(|p| pattern_base_dir(p))
-            .filter_map(|d| fs::canonicalize(d).ok())
+            .filter_map(|(_, p)| {
+                let dir = fs::canonicalize(pattern_base_dir(p)).ok()?;
+                Some((dir, pattern_depth(p).is_none_or(|depth| depth > 1)))
+            })
             .collect()
     }
 
@@ -167,13 +296,45 @@ impl App {
     /// same as a glob not matching it under `watch ncount src/*`. It comes
     /// back on its own when the watcher reports it again and a reload
     /// succeeds.
+    ///
+    /// Sections whose numbers the reload changed are remembered for a few
+    /// seconds (see `change`), so the table can point them out; sections it
+    /// removed are named in the footer. A file coming back from hiding
+    /// isn't news, so nothing is marked then.
     pub fn reload(&mut self, path: &Path) {
         let Some(file) = self.files.iter_mut().find(|f| f.path == path) else {
             return;
         };
+        let old = file.document.take();
         file.document = read_with_retries(&file.path)
             .ok()
             .map(|text| build_document(&self.text_filter, &text));
+
+        let (Some(old), Some(new)) = (old, &file.document) else {
+            return;
+        };
+        let new = flatten_document(path, new);
+        let (changes, removed) = diff_rows(&flatten_document(path, &old), &new);
+        let now = Instant::now();
+        self.changes
+            .retain(|_, (_, when)| now.duration_since(*when) < HIGHLIGHT_FOR);
+        self.changes.extend(
+            changes
+                .into_iter()
+                .map(|(row, change)| (position_key(row), (change, now))),
+        );
+        if !removed.is_empty() {
+            self.notice = Some(format!("removed: {}", removed.join(", ")));
+        }
+    }
+
+    /// How the latest reload changed `row`'s section, if it did so only
+    /// moments ago.
+    pub fn change(&self, row: &RowData) -> Option<Change> {
+        self.changes
+            .get(&position_key(row))
+            .filter(|(_, when)| when.elapsed() < HIGHLIGHT_FOR)
+            .map(|&(change, _)| change)
     }
 
     /// Re-expand the live glob patterns and reconcile the file set with
@@ -190,17 +351,22 @@ impl App {
             return false;
         }
 
-        let mut matched = HashSet::new();
-        for pattern in &self.patterns {
-            matched.extend(expand_pattern(pattern));
+        // A file matched by more than one pattern takes the first's rank.
+        let mut matched = Vec::<(usize, PathBuf)>::new();
+        for (rank, pattern) in &self.patterns {
+            for path in expand_pattern(pattern, &self.walk) {
+                if !matched.iter().any(|(_, p)| *p == path) {
+                    matched.push((*rank, path));
+                }
+            }
         }
 
         let before = self.files.len();
         self.files
-            .retain(|f| !f.from_pattern || matched.contains(&f.path));
+            .retain(|f| !f.from_pattern || matched.iter().any(|(_, p)| *p == f.path));
         let mut changed = self.files.len() != before;
 
-        for path in matched {
+        for (rank, path) in matched {
             if self.files.iter().any(|f| f.path == path) {
                 // Already tracked. A file present but unreadable (document
                 // is None) is deliberately NOT retried here — that would
@@ -216,18 +382,19 @@ impl App {
                 path,
                 document,
                 from_pattern: true,
+                rank,
             });
             changed = true;
         }
 
         if changed {
-            self.files.sort_by(|a, b| a.path.cmp(&b.path));
+            self.order.sort(&mut self.files, |f| (f.rank, &f.path));
         }
         changed
     }
 
-    /// The currently visible rows: a filtered subtree if `filter` matches
-    /// something, otherwise every file chained in order. Mirrors the CLI's
+    /// The currently visible rows: every section `filter` matches, in every
+    /// file, if it matches any, otherwise every file chained in order. Mirrors the CLI's
     /// `StatFmt::apply_filter` fallback, including the "no match" warning.
     /// Files that currently can't be read (see `reload`) contribute no rows.
     pub fn rows(&mut self) -> Vec<RowData> {
@@ -278,19 +445,35 @@ impl App {
 
     fn collect_rows(&mut self) -> Vec<RowData> {
         let rows = if let Some(filter) = self.filter.clone() {
-            let needle = filter.to_ascii_uppercase();
-            for file in &self.files {
-                let Some(document) = &file.document else {
-                    continue;
-                };
-                if let Some(found) = document.get_heading(&needle) {
-                    self.status = None;
-                    return self.visible_rows(flatten_document(&file.path, found));
+            if self
+                .matcher
+                .as_ref()
+                .is_none_or(|(compiled, _)| *compiled != filter)
+            {
+                let matcher = Matcher::new(self.match_mode, &filter).map_err(|e| e.to_string());
+                self.matcher = Some((filter.clone(), matcher));
+            }
+            let (_, matcher) = self.matcher.as_ref().expect("compiled above");
+            match matcher {
+                Ok(matcher) => {
+                    let found: Vec<_> = self
+                        .files
+                        .iter()
+                        .filter_map(|file| Some((&file.path, file.document.as_ref()?)))
+                        .flat_map(|(path, document)| {
+                            flatten_found(path, document, &document.find_headings(matcher))
+                        })
+                        .collect();
+                    if !found.is_empty() {
+                        self.status = None;
+                        return self.visible_rows(found);
+                    }
+                    self.status = Some(format!(
+                        "no heading matching {filter:?} found; showing everything"
+                    ));
                 }
+                Err(e) => self.status = Some(format!("{e}; showing everything")),
             }
-            self.status = Some(format!(
-                "no heading matching {filter:?} found; showing everything"
-            ));
             self.all_rows()
         } else {
             self.status = None;
@@ -313,6 +496,7 @@ impl App {
     }
 
     fn visible_rows(&self, rows: Vec<RowData>) -> Vec<RowData> {
+        let rows = sort_rows(rows, self.sort, self.descending);
         let mut structural_keys = HashSet::new();
         let mut visible = Vec::new();
 
@@ -460,6 +644,34 @@ impl App {
         }
     }
 
+    /// Asks for the selected section to be opened in the editor, at its
+    /// heading.
+    pub fn edit_selected(&mut self, rows: &[RowData]) {
+        if let Some(row) = self.selected_row(rows) {
+            self.edit_request = Some((row.path.clone(), row.location.line));
+        }
+    }
+
+    /// Moves on to the next sort order, keeping the selection on the same
+    /// section wherever it lands.
+    pub fn cycle_sort(&mut self, rows: &[RowData]) {
+        self.sort = self.sort.next();
+        self.follow_selection(rows);
+    }
+
+    pub fn toggle_sort_direction(&mut self, rows: &[RowData]) {
+        self.descending = !self.descending;
+        self.follow_selection(rows);
+    }
+
+    /// Has `rows` put the selection back on the selected section once the
+    /// table has been reordered.
+    fn follow_selection(&mut self, rows: &[RowData]) {
+        if self.deferred_selection.is_none() {
+            self.deferred_selection = self.selected_row(rows).map(row_key);
+        }
+    }
+
     fn selected_row<'a>(&self, rows: &'a [RowData]) -> Option<&'a RowData> {
         self.table_state.selected().and_then(|i| rows.get(i))
     }
@@ -499,23 +711,54 @@ impl App {
     }
 }
 
-fn flatten_document(path: &Path, document: &Document) -> Vec<RowData> {
+pub(super) fn flatten_document(path: &Path, document: &Document) -> Vec<RowData> {
     let mut rows = Vec::new();
     let stats = document.stats();
-    if stats.heading().is_some() {
-        flatten_stats(path, stats, None, &mut rows);
-    } else {
-        for child in stats.children() {
-            flatten_stats(path, child, None, &mut rows);
+    match stats.heading() {
+        Some(heading) => {
+            let position = vec![(heading.to_string(), 0)];
+            flatten_stats(path, stats, None, position, &mut rows);
         }
+        None => flatten_children(path, stats, None, &[], &mut rows),
     }
     rows
 }
 
+/// The rows of the sections `found` in `document` (see
+/// `Document::find_headings`), each followed by its subsections. Each
+/// keeps its position in the whole file, but, with no parent row shown,
+/// none of them has one.
+fn flatten_found(path: &Path, document: &Document, found: &[&Document]) -> Vec<RowData> {
+    let starts: HashSet<usize> = found
+        .iter()
+        .map(|section| section.stats().location().offset)
+        .collect();
+    let mut within: Option<SectionPath> = None;
+    flatten_document(path, document)
+        .into_iter()
+        .filter_map(|mut row| {
+            if within
+                .as_ref()
+                .is_some_and(|top| row.position.starts_with(top))
+            {
+                return Some(row);
+            }
+            within = None;
+            if !starts.contains(&row.location.offset) {
+                return None;
+            }
+            within = Some(row.position.clone());
+            row.parent = None;
+            Some(row)
+        })
+        .collect()
+}
+
 fn flatten_stats(
     path: &Path,
     stats: crate::document::DocumentStats<'_>,
     parent: Option<(PathBuf, String)>,
+    position: SectionPath,
     rows: &mut Vec<RowData>,
 ) {
     let next_parent = if let Some(heading) = stats.heading() {
@@ -525,8 +768,10 @@ fn flatten_stats(
             path: path.to_path_buf(),
             heading: heading.to_string(),
             level: stats.level(),
+            location: stats.location(),
             paragraphs: stats.paragraphs(),
             parent,
+            position: position.clone(),
             subtree_paragraphs,
             has_children: stats.has_children(),
             pinned_exception: false,
@@ -535,9 +780,25 @@ fn flatten_stats(
     } else {
         parent
     };
+    flatten_children(path, stats, next_parent, &position, rows);
+}
 
+fn flatten_children(
+    path: &Path,
+    stats: crate::document::DocumentStats<'_>,
+    parent: Option<(PathBuf, String)>,
+    position: &[(String, usize)],
+    rows: &mut Vec<RowData>,
+) {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
     for child in stats.children() {
-        flatten_stats(path, child, next_parent.clone(), rows);
+        let mut position = position.to_vec();
+        if let Some(heading) = child.heading() {
+            let count = seen.entry(heading).or_default();
+            position.push((heading.to_string(), *count));
+            *count += 1;
+        }
+        flatten_stats(path, child, parent.clone(), position, rows);
     }
 }
 
@@ -545,6 +806,89 @@ fn row_key(row: &RowData) -> (PathBuf, String) {
     (row.path.clone(), row.heading.clone())
 }
 
+fn position_key(row: &RowData) -> (PathBuf, SectionPath) {
+    (row.path.clone(), row.position.clone())
+}
+
+/// `rows` (in document order, as `flatten_document` gives them) with each
+/// set of siblings sorted by `sort`, each section still followed by its own
+/// subtree. Ties stay in document order.
+fn sort_rows(rows: Vec<RowData>, sort: Sort, descending: bool) -> Vec<RowData> {
+    if sort == Sort::Document {
+        return rows;
+    }
+
+    // Rebuild the tree from the parent links: `children[i]` are row `i`'s
+    // children, in document order.
+    let mut roots = Vec::new();
+    let mut children = vec![Vec::new(); rows.len()];
+    let mut ancestors: Vec<usize> = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        while let Some(&top) = ancestors.last() {
+            if row.parent.as_ref() == Some(&row_key(&rows[top])) {
+                break;
+            }
+            ancestors.pop();
+        }
+        match ancestors.last() {
+            Some(&parent) => children[parent].push(i),
+            None => roots.push(i),
+        }
+        ancestors.push(i);
+    }
+
+    let by_key = |a: &usize, b: &usize| {
+        let (a, b) = (sort.key(&rows[*a]), sort.key(&rows[*b]));
+        if descending { b.cmp(&a) } else { a.cmp(&b) }
+    };
+    roots.sort_by(by_key);
+    for siblings in &mut children {
+        siblings.sort_by(by_key);
+    }
+
+    let mut order = Vec::with_capacity(rows.len());
+    let mut pending: Vec<usize> = roots.into_iter().rev().collect();
+    while let Some(i) = pending.pop() {
+        order.push(i);
+        pending.extend(children[i].iter().rev());
+    }
+
+    let mut rows: Vec<_> = rows.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| rows[i].take()).collect()
+}
+
+/// What changed between two versions of a file's rows: each section whose
+/// own paragraphs changed or that is new, and the headings of those that
+/// are gone. Sections are matched up by position, so of two "Interlude"s,
+/// the first is compared with the first.
+fn diff_rows<'a>(old: &[RowData], new: &'a [RowData]) -> (Vec<(&'a RowData, Change)>, Vec<String>) {
+    let before: HashMap<_, _> = old
+        .iter()
+        .map(|row| (&row.position, row.paragraphs))
+        .collect();
+    let changes = new
+        .iter()
+        .filter_map(|row| {
+            let change = match before.get(&row.position) {
+                None => Change::Added,
+                Some(paragraphs) if *paragraphs != row.paragraphs => {
+                    Change::Words(i64::from(row.paragraphs.total) - i64::from(paragraphs.total))
+                }
+                Some(_) => return None,
+            };
+            Some((row, change))
+        })
+        .collect();
+
+    let after: HashSet<_> = new.iter().map(|row| &row.position).collect();
+    let removed = old
+        .iter()
+        .filter(|row| !after.contains(&row.position))
+        .map(|row| row.heading.clone())
+        .collect();
+    (changes, removed)
+}
+
 fn build_document(filter: &TextFilter, text: &str) -> Document {
     let mut builder = DocumentBuilder::new();
     builder.apply(filter.lex(text));
@@ -559,17 +903,29 @@ fn build_document(filter: &TextFilter, text: &str) -> Document {
 const RETRY_DELAYS: [Duration; 2] = [Duration::from_millis(10), Duration::from_millis(75)];
 
 fn read_with_retries(path: &Path) -> io::Result<String> {
-    let mut result = fs::read_to_string(path);
+    let mut result = read_text(path);
     for delay in RETRY_DELAYS {
         if result.is_ok() {
             break;
         }
         std::thread::sleep(delay);
-        result = fs::read_to_string(path);
+        result = read_text(path);
     }
     result
 }
 
+/// `None` for a file that isn't text (see `read_text`).
+fn read_startup(path: &Path) -> io::Result<Option<String>> {
+    match read_text(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            log::warn(format_args!("skipped {}: not a text file", path.display()));
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
 /// Scroll the viewport down one page while keeping the cursor on the same
 /// screen row. Returns the new `(offset, selected)`. The viewport scrolls
 /// until its last page is full (`max_offset = row_count - page_size`); at
@@ -637,8 +993,10 @@ mod tests {
     }
 
     fn test_app(files: Vec<LoadedFile>, patterns: Vec<String>) -> App {
+        let patterns = patterns.into_iter().map(|p| (0, p)).collect();
         let mut table_state = TableState::default();
         table_state.select(Some(0));
+        let session = Session::start(&files);
         App {
             files,
             filter: None,
@@ -649,9 +1007,22 @@ mod tests {
             deferred_selection: None,
             last_rows_keys: Vec::new(),
             status: None,
+            notice: None,
             should_quit: false,
+            edit_request: None,
+            goals: Goals::default(),
+            columns: Vec::new(),
+            rates: Rates::default(),
+            session,
+            sort: Sort::Document,
+            descending: true,
+            changes: HashMap::new(),
             patterns,
+            walk: Walk::default(),
+            order: FileOrder::default(),
             text_filter: TextFilter::new(),
+            match_mode: MatchMode::default(),
+            matcher: None,
         }
     }
 
@@ -662,6 +1033,7 @@ mod tests {
             path: path.to_path_buf(),
             document: Some(document),
             from_pattern: false,
+            rank: 0,
         }
     }
 
@@ -697,6 +1069,127 @@ mod tests {
         assert_eq!(rows[0].paragraphs.total, 5);
     }
 
+    #[test]
+    fn reload_marks_what_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut app, path) = app_with_file(
+            &dir,
+            "ch1.md",
+            "# One\n\nalpha beta\n\n## Two\n\ngamma\n\n## Three\n\ndelta\n",
+        );
+
+        fs::write(
+            &path,
+            "# One\n\nalpha beta\n\n## Two\n\ngamma delta epsilon\n\n## Four\n\nzeta\n",
+        )
+        .unwrap();
+        app.reload(&path);
+        app.expanded.insert((path.clone(), String::from("One")));
+
+        let rows = app.rows();
+        let changes: Vec<_> = rows.iter().map(|row| app.change(row)).collect();
+        assert_eq!(changes, [None, Some(Change::Words(2)), Some(Change::Added)]);
+        assert_eq!(app.notice.as_deref(), Some("removed: Three"));
+    }
+
+    #[test]
+    fn reload_tells_repeated_headings_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut app, path) = app_with_file(
+            &dir,
+            "ch1.md",
+            "# Break\n\na\n\n# Break\n\nb c\n\n# Break\n\nd\n",
+        );
+
+        fs::write(&path, "# Break\n\na\n\n# Break\n\nb c d e\n").unwrap();
+        app.reload(&path);
+
+        let rows = app.rows();
+        let changes: Vec<_> = rows.iter().map(|row| app.change(row)).collect();
+        assert_eq!(changes, [None, Some(Change::Words(2))]);
+        assert_eq!(app.notice.as_deref(), Some("removed: Break"));
+        let deltas: Vec<_> = rows
+            .iter()
+            .map(|row| app.session.row_words(row, false))
+            .collect();
+        assert_eq!(deltas, [0, 2]);
+    }
+
+    #[test]
+    fn sorting_ranks_siblings_within_their_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut app, path) = app_with_file(
+            &dir,
+            "ch1.md",
+            "# Short\n\na\n\n## A\n\nb\n\n## B\n\nc d e\n\n\
+             # Long\n\nf g h i\n\n## C\n\nj k\n",
+        );
+        for heading in ["Short", "Long"] {
+            app.expanded.insert((path.clone(), String::from(heading)));
+        }
+        let headings = |app: &mut App| -> Vec<String> {
+            app.rows().into_iter().map(|row| row.heading).collect()
+        };
+        assert_eq!(headings(&mut app), ["Short", "A", "B", "Long", "C"]);
+
+        let rows = app.rows();
+        app.select_index(1);
+        app.cycle_sort(&rows);
+        assert_eq!(app.sort, Sort::Words);
+        assert_eq!(headings(&mut app), ["Long", "C", "Short", "B", "A"]);
+        // The selection stays on "A" wherever it went.
+        assert_eq!(app.table_state.selected(), Some(4));
+
+        let rows = app.rows();
+        app.toggle_sort_direction(&rows);
+        assert_eq!(headings(&mut app), ["Short", "A", "B", "Long", "C"]);
+    }
+
+    #[test]
+    fn patterns_reaching_into_subdirectories_are_watched_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app_with_files(&dir, &[("ch1.md", "a\n")]);
+        let base = dir.path().display();
+        app.patterns = vec![
+            (0, format!("{base}/*/ch-*/scene-*.md")),
+            (1, format!("{base}/*.md")),
+        ];
+
+        let base = dir.path().canonicalize().unwrap();
+        assert_eq!(app.pattern_dirs(), [(base.clone(), true), (base, false)]);
+    }
+
+    #[test]
+    fn filter_shows_every_match_in_every_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app_with_files(
+            &dir,
+            &[
+                (
+                    "ch1.md",
+                    "# One\n\na\n\n## From the Book of Shadows\n\nb c\n",
+                ),
+                (
+                    "ch2.md",
+                    "# Two\n\nd\n\n## From the Book of Shadows\n\ne f g\n",
+                ),
+            ],
+        );
+        app.match_mode = MatchMode::Substring;
+        app.filter = Some(String::from("shadows"));
+
+        let rows = app.rows();
+        let totals: Vec<_> = rows.iter().map(|row| row.paragraphs.total).collect();
+        assert_eq!(totals, [2, 3]);
+        assert_eq!(app.status, None);
+
+        // A new filter is compiled afresh, not served from the old one.
+        app.filter = Some(String::from("two"));
+        let rows = app.rows();
+        let headings: Vec<_> = rows.iter().map(|row| row.heading.as_str()).collect();
+        assert_eq!(headings, ["Two"]);
+    }
+
     #[test]
     fn reload_hides_a_file_that_disappears() {
         let dir = tempfile::tempdir().unwrap();
@@ -1037,6 +1530,7 @@ mod tests {
                 path: path.clone(),
                 document: Some(document),
                 from_pattern: false,
+                rank: 0,
             }],
             vec![base.join("*.md").to_string_lossy().into_owned()],
         );
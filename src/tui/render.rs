@@ -1,22 +1,27 @@
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Layout, Rect};
-use ratatui::style::{Modifier, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table};
 
 use crate::document::Paragraphs;
+use crate::estimate::duration_label;
+use crate::fmt::{Column, location};
+use crate::goals::Progress;
 
-use super::app::{App, Mode, RowData};
+use super::app::{App, Change, Mode, RowData, Sort};
+use super::session::{delta_label, words_per_minute};
 
 /// Right-aligns numeric columns; the heading column stays left-aligned.
 fn right(s: impl Into<String>) -> Cell<'static> {
     Cell::from(Line::from(s.into()).right_aligned())
 }
 
-const COMPACT_WIDTHS: [Constraint; 3] = [
+const COMPACT_WIDTHS: [Constraint; 4] = [
     Constraint::Fill(1),
     Constraint::Length(WORDS_WIDTH),
     Constraint::Length(TOTAL_WIDTH),
+    Constraint::Length(DELTA_WIDTH),
 ];
 
 const COLUMN_SPACING: u16 = 1;
@@ -25,14 +30,33 @@ const AVG_WIDTH: u16 = 6;
 const LONG_WIDTH: u16 = 7;
 const WORDS_WIDTH: u16 = 8;
 const TOTAL_WIDTH: u16 = 9;
+const DELTA_WIDTH: u16 = 6;
 
-const VERBOSE_WIDTHS: [Constraint; 6] = [
+const VERBOSE_WIDTHS: [Constraint; 7] = [
     Constraint::Fill(1),
     Constraint::Length(COUNT_WIDTH),
     Constraint::Length(AVG_WIDTH),
     Constraint::Length(LONG_WIDTH),
     Constraint::Length(WORDS_WIDTH),
     Constraint::Length(TOTAL_WIDTH),
+    Constraint::Length(DELTA_WIDTH),
+];
+
+/// Room for an extra stats column's label and values alike.
+fn column_width(column: Column) -> u16 {
+    match column {
+        Column::Location => 24,
+        _ => (column.label().chars().count() as u16).max(6),
+    }
+}
+
+const PERCENT_WIDTH: u16 = 5;
+const LEFT_WIDTH: u16 = 7;
+
+/// Appended to either layout when the app has per-section goals.
+const GOAL_WIDTHS: [Constraint; 2] = [
+    Constraint::Length(PERCENT_WIDTH),
+    Constraint::Length(LEFT_WIDTH),
 ];
 
 /// `[table_area, footer_area]` for the app's vertical split (everything but
@@ -46,6 +70,7 @@ pub fn draw(frame: &mut Frame, app: &mut App, rows: &[RowData]) {
     let [table_area, footer_area] = main_areas(frame.area());
 
     let selected = app.table_state.selected();
+    let show_goals = app.goals.has_section_goals();
     let show_detail = rows.iter().enumerate().any(|(i, row)| {
         Some(i) == selected
             || app
@@ -53,7 +78,7 @@ pub fn draw(frame: &mut Frame, app: &mut App, rows: &[RowData]) {
                 .contains(&(row.path.clone(), row.heading.clone()))
     });
 
-    let mut running = 0u32;
+    let mut running = Paragraphs::default();
     let table_rows: Vec<Row> = rows
         .iter()
         .enumerate()
@@ -70,46 +95,88 @@ pub fn draw(frame: &mut Frame, app: &mut App, rows: &[RowData]) {
             // Avg, Long) reflect every paragraph under it. Unfolded, the row
             // shows only its own direct paragraphs — the children carry their
             // own rows. This mirrors the existing Words logic exactly.
-            let detail = if row.has_children && !unfolded {
+            let folded = row.has_children && !unfolded;
+            let detail = if folded {
                 row.subtree_paragraphs
             } else {
                 row.paragraphs
             };
-            let words = detail.total;
             if !row.pinned_exception {
-                running += words;
+                running.merge(detail);
             }
-            build_row(row, detail, words, running, verbose, pinned, show_detail)
+            // Like the CLI, a goal covers the section's whole subtree,
+            // folded or not.
+            let progress = show_goals
+                .then(|| app.goals.target(&row.heading, row.level))
+                .flatten()
+                .map(|target| Progress::new(row.subtree_paragraphs.total, target));
+            let mut cells = build_row(
+                row,
+                detail,
+                running.total,
+                verbose,
+                pinned,
+                show_detail,
+                app,
+            );
+            cells.push(delta_cell(app.session.row_words(row, folded)));
+            if show_goals {
+                cells.extend(goal_cells(progress));
+            }
+            Row::new(cells).style(change_style(app, row))
         })
         .collect();
 
-    let header = if show_detail {
-        Row::new([
+    let mut header = if show_detail {
+        let mut header = vec![
             Cell::from(" §"),
-            right("Count¶"),
-            right("Avg¶"),
-            right("Long¶"),
-            right("Words"),
+            right(sort_label(app, Sort::Paragraphs, "Count¶")),
+            right(sort_label(app, Sort::AverageLength, "Avg¶")),
+            right(sort_label(app, Sort::Longest, "Long¶")),
+        ];
+        header.extend(app.columns.iter().map(|column| right(column.label())));
+        header.extend([
+            right(sort_label(app, Sort::Words, "Words")),
             right("Total"),
-        ])
+            right("Δ"),
+        ]);
+        header
     } else {
-        Row::new(vec![Cell::from(" §"), right("Words"), right("Total")])
-    }
-    .style(Style::new().add_modifier(Modifier::BOLD));
-
-    let table = if show_detail {
-        Table::new(table_rows, VERBOSE_WIDTHS).column_spacing(COLUMN_SPACING)
+        vec![
+            Cell::from(" §"),
+            right(sort_label(app, Sort::Words, "Words")),
+            right("Total"),
+            right("Δ"),
+        ]
+    };
+    let mut widths = if show_detail {
+        let mut widths = VERBOSE_WIDTHS.to_vec();
+        let extra = app
+            .columns
+            .iter()
+            .map(|&column| Constraint::Length(column_width(column)));
+        widths.splice(4..4, extra);
+        widths
     } else {
-        Table::new(table_rows, COMPACT_WIDTHS).column_spacing(COLUMN_SPACING)
+        COMPACT_WIDTHS.to_vec()
+    };
+    if show_goals {
+        header.extend([right("%"), right("Left")]);
+        widths.extend(GOAL_WIDTHS);
     }
-    .header(header)
-    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let header = Row::new(header).style(Style::new().add_modifier(Modifier::BOLD));
+
+    let table = Table::new(table_rows, widths)
+        .column_spacing(COLUMN_SPACING)
+        .header(header)
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
 
     frame.render_stateful_widget(table, table_area, &mut app.table_state);
     if show_detail {
-        render_compact_headings(frame, table_area, app, rows, selected);
+        render_compact_headings(frame, table_area, app, rows, selected, show_goals);
     }
-    render_footer(frame, footer_area, app, running);
+    let selected_row = selected.and_then(|i| rows.get(i));
+    render_footer(frame, footer_area, app, running.total, selected_row);
 
     if matches!(app.mode, Mode::Help) {
         let area = frame.area();
@@ -118,7 +185,7 @@ pub fn draw(frame: &mut Frame, app: &mut App, rows: &[RowData]) {
 }
 
 /// The verbose table keeps its numeric columns aligned globally, but compact
-/// rows have no values in Count/Avg/Long. Paint those rows' headings across
+/// rows have no values in Count/Avg/Long (or the extra columns). Paint those rows' headings across
 /// the otherwise-empty columns, stopping before Words, so their titles get
 /// the space they would have had in the compact table.
 fn render_compact_headings(
@@ -127,8 +194,9 @@ fn render_compact_headings(
     app: &App,
     rows: &[RowData],
     selected: Option<usize>,
+    show_goals: bool,
 ) {
-    let heading_width = verbose_heading_span_width(table_area.width);
+    let heading_width = verbose_heading_span_width(table_area.width, show_goals);
     if heading_width == 0 {
         return;
     }
@@ -152,9 +220,9 @@ fn render_compact_headings(
         }
 
         let indent = "  ".repeat(row.level.saturating_sub(1).max(0) as usize);
-        let heading = format!("  {indent}{}", row.heading);
+        let heading = format!("  {indent}{}{}", row.heading, change_badge(app, row));
         let y = first_row_y + row_offset as u16;
-        let style = Style::default();
+        let style = change_style(app, row);
         for x in table_area.x..table_area.x + heading_width {
             buffer[(x, y)].set_symbol(" ").set_style(style);
         }
@@ -164,29 +232,37 @@ fn render_compact_headings(
 
 /// Width from the left edge of a verbose table to the start of its Words
 /// column, including the blank detail columns and their spacing.
-fn verbose_heading_span_width(table_width: u16) -> u16 {
-    table_width.saturating_sub(WORDS_WIDTH + COLUMN_SPACING + TOTAL_WIDTH)
+fn verbose_heading_span_width(table_width: u16, show_goals: bool) -> u16 {
+    let goals = if show_goals {
+        PERCENT_WIDTH + COLUMN_SPACING + LEFT_WIDTH + COLUMN_SPACING
+    } else {
+        0
+    };
+    table_width.saturating_sub(
+        WORDS_WIDTH + COLUMN_SPACING + TOTAL_WIDTH + COLUMN_SPACING + DELTA_WIDTH + goals,
+    )
 }
 
 fn build_row(
     row: &RowData,
     detail: Paragraphs,
-    words: u32,
     running_total: u32,
     verbose: bool,
     pinned: bool,
     show_detail: bool,
-) -> Row<'static> {
+    app: &App,
+) -> Vec<Cell<'static>> {
+    let words = detail.total;
     let indent = "  ".repeat(row.level.saturating_sub(1).max(0) as usize);
     let marker = if pinned { "●" } else { " " };
-    let heading = format!("{marker} {indent}{}", row.heading);
+    let heading = format!("{marker} {indent}{}{}", row.heading, change_badge(app, row));
 
     if !show_detail {
-        return Row::new([
+        return vec![
             Cell::from(heading),
             right(words.to_string()),
             right(running_total.to_string()),
-        ]);
+        ];
     }
 
     let (count, avg, max) = if verbose {
@@ -199,29 +275,135 @@ fn build_row(
         (String::new(), String::new(), String::new())
     };
 
-    Row::new([
-        Cell::from(heading),
-        right(count),
-        right(avg),
-        right(max),
-        right(words.to_string()),
-        right(running_total.to_string()),
-    ])
+    let mut cells = vec![Cell::from(heading), right(count), right(avg), right(max)];
+    let at = (verbose && app.columns.contains(&Column::Location))
+        .then(|| location(Some(&row.path), row.location.line));
+    cells.extend(app.columns.iter().map(|column| {
+        right(if verbose {
+            column.value(&detail, &app.rates, at.as_deref())
+        } else {
+            String::new()
+        })
+    }));
+    cells.extend([right(words.to_string()), right(running_total.to_string())]);
+    cells
+}
+
+/// A column's label, with an arrow when the table is sorted by it.
+fn sort_label(app: &App, sort: Sort, label: &str) -> String {
+    match (app.sort == sort, app.descending) {
+        (false, _) => label.to_string(),
+        (true, true) => format!("{label}↓"),
+        (true, false) => format!("{label}↑"),
+    }
 }
 
-fn render_footer(frame: &mut Frame, area: Rect, app: &App, running_total: u32) {
+/// After the heading of a section the latest reload changed: how many
+/// words it gained or lost, or that it's new.
+fn change_badge(app: &App, row: &RowData) -> String {
+    match app.change(row) {
+        Some(Change::Words(words)) => match delta_label(words).as_str() {
+            "" => String::new(),
+            label => format!("  {label}"),
+        },
+        Some(Change::Added) => String::from("  new"),
+        None => String::new(),
+    }
+}
+
+/// Rows the latest reload changed stand out for a few seconds.
+fn change_style(app: &App, row: &RowData) -> Style {
+    match app.change(row) {
+        Some(_) => Style::new().fg(Color::Yellow),
+        None => Style::new(),
+    }
+}
+
+/// A section's words gained (green) or lost (red) this session.
+fn delta_cell(words: i64) -> Cell<'static> {
+    let style = match words {
+        1.. => Style::new().fg(Color::Green),
+        ..0 => Style::new().fg(Color::Red),
+        0 => Style::new(),
+    };
+    right(delta_label(words)).style(style)
+}
+
+/// Percent complete and words left (`+N` once over), green once the goal
+/// is met; blank for a section without a goal.
+fn goal_cells(progress: Option<Progress>) -> [Cell<'static>; 2] {
+    let Some(progress) = progress else {
+        return [Cell::default(), Cell::default()];
+    };
+    let style = if progress.is_met() {
+        Style::new().fg(Color::Green)
+    } else {
+        Style::new()
+    };
+    [
+        right(progress.percent_label()).style(style),
+        right(progress.left_label()).style(style),
+    ]
+}
+
+/// The footer: the running total and its estimates on the left, and on the
+/// right, where the selected section starts (`file:line`) and the help key.
+fn render_footer(
+    frame: &mut Frame,
+    area: Rect,
+    app: &App,
+    running_total: u32,
+    selected: Option<&RowData>,
+) {
     match &app.mode {
         Mode::Filter { buffer, .. } => {
             frame.render_widget(Paragraph::new(format!("/{buffer}")), area);
         }
         Mode::Normal | Mode::Help => {
-            if let Some(status) = &app.status {
+            if let Some(status) = app.notice.as_ref().or(app.status.as_ref()) {
                 frame.render_widget(Paragraph::new(status.clone()), area);
             } else {
-                let [left, right] =
-                    Layout::horizontal([Constraint::Min(0), Constraint::Min(0)]).areas(area);
-                frame.render_widget(Paragraph::new(format!("{running_total} words")), left);
-                frame.render_widget(Paragraph::new("? help").right_aligned(), right);
+                let hint = match selected {
+                    Some(row) => {
+                        format!("{} · ? help", location(Some(&row.path), row.location.line))
+                    }
+                    None => String::from("? help"),
+                };
+                let [left, right] = Layout::horizontal([
+                    Constraint::Min(0),
+                    Constraint::Length(hint.chars().count() as u16),
+                ])
+                .areas(area);
+                let words = match app.goals.total.filter(|_| app.filter.is_none()) {
+                    Some(target) => {
+                        let progress = Progress::new(running_total, target);
+                        format!(
+                            "{running_total} / {target} words ({})",
+                            progress.percent_label()
+                        )
+                    }
+                    None => format!("{running_total} words"),
+                };
+                let session = app.session.words(&app.files);
+                let elapsed = app.session.elapsed();
+                let words = format!(
+                    "{words} · {} this session, {} wpm in {}",
+                    match delta_label(session).as_str() {
+                        "" => "±0",
+                        label => label,
+                    },
+                    words_per_minute(session, elapsed),
+                    duration_label((elapsed.as_secs() / 60) as u32),
+                );
+                let rates = &app.rates;
+                let words = format!(
+                    "{words} · {} pages · {} reading · {} narration",
+                    rates.pages(running_total),
+                    duration_label(rates.reading_minutes(running_total)),
+                    duration_label(rates.narration_minutes(running_total)),
+                );
+                frame.render_widget(Paragraph::new(words), left);
+                frame.render_widget(Paragraph::new(hint).right_aligned(), right);
             }
         }
     }
@@ -271,7 +453,12 @@ fn help_lines() -> Vec<Line<'static>> {
         help_entry("Enter", "apply the filter"),
         help_entry("Esc", "cancel the filter"),
         Line::default(),
+        help_group("Sorting"),
+        help_entry("s", "sort by words, ¶ count, avg ¶, long ¶"),
+        help_entry("S", "reverse the sort"),
+        Line::default(),
         help_group("General"),
+        help_entry("e", "edit the selected section in $EDITOR"),
         help_entry("?", "show this help"),
         help_entry("q / Esc / Ctrl-C", "quit"),
     ]
@@ -305,11 +492,30 @@ mod tests {
     use super::*;
 
     #[test]
-    fn compact_heading_span_stops_before_words_and_total() {
+    fn compact_heading_span_stops_before_words_total_and_delta() {
+        let width = 60;
+        let heading = verbose_heading_span_width(width, false);
+        assert_eq!(heading, 35);
+        assert_eq!(
+            heading + WORDS_WIDTH + TOTAL_WIDTH + DELTA_WIDTH + 2 * COLUMN_SPACING,
+            width
+        );
+    }
+
+    #[test]
+    fn compact_heading_span_also_stops_before_goal_columns() {
         let width = 60;
-        let heading = verbose_heading_span_width(width);
-        assert_eq!(heading, 42);
-        assert_eq!(heading + WORDS_WIDTH + COLUMN_SPACING + TOTAL_WIDTH, width);
+        let heading = verbose_heading_span_width(width, true);
+        assert_eq!(
+            heading
+                + WORDS_WIDTH
+                + TOTAL_WIDTH
+                + DELTA_WIDTH
+                + PERCENT_WIDTH
+                + LEFT_WIDTH
+                + 4 * COLUMN_SPACING,
+            width
+        );
     }
 
     #[test]
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::app::{LoadedFile, RowData, SectionPath, flatten_document};
+
+/// What the manuscript looked like when watch mode started, so the table
+/// can show what's been written since: overall, and section by section.
+pub struct Session {
+    started: Instant,
+    total: u32,
+    /// Each section's own words and its whole subtree's, keyed by file and
+    /// position, so repeated headings each have their own.
+    sections: HashMap<(PathBuf, SectionPath), (u32, u32)>,
+}
+
+impl Session {
+    pub fn start(files: &[LoadedFile]) -> Self {
+        let mut sections = HashMap::new();
+        for file in files {
+            let Some(document) = &file.document else {
+                continue;
+            };
+            for row in flatten_document(&file.path, document) {
+                sections.insert(
+                    (row.path, row.position),
+                    (row.paragraphs.total, row.subtree_paragraphs.total),
+                );
+            }
+        }
+        Self {
+            started: Instant::now(),
+            total: total_words(files),
+            sections,
+        }
+    }
+
+    /// Words added (or, below zero, cut) across every file since the start.
+    pub fn words(&self, files: &[LoadedFile]) -> i64 {
+        i64::from(total_words(files)) - i64::from(self.total)
+    }
+
+    /// The change in the words a row shows: its subtree's when `subtree` is
+    /// set (a folded parent), otherwise its own. A section that didn't
+    /// exist at the start has changed by all of them.
+    pub fn row_words(&self, row: &RowData, subtree: bool) -> i64 {
+        let key = (row.path.clone(), row.position.clone());
+        let (own, all) = self.sections.get(&key).copied().unwrap_or_default();
+        let (now, then) = if subtree {
+            (row.subtree_paragraphs.total, all)
+        } else {
+            (row.paragraphs.total, own)
+        };
+        i64::from(now) - i64::from(then)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+/// Every section's words, the way the table totals them.
+fn total_words(files: &[LoadedFile]) -> u32 {
+    files
+        .iter()
+        .filter_map(|file| file.document.as_ref())
+        .flat_map(|document| document.iter())
+        .map(|stats| stats.paragraphs().total)
+        .sum()
+}
+
+/// Words per minute over `elapsed`, counting only words added. The first
+/// minute counts as a whole one, so a quick paste doesn't read as 4000 wpm.
+pub fn words_per_minute(words: i64, elapsed: Duration) -> u32 {
+    let minutes = (elapsed.as_secs_f64() / 60.0).max(1.0);
+    (words.max(0) as f64 / minutes).round() as u32
+}
+
+/// `+12` or `-3`; nothing at all for no change.
+pub fn delta_label(words: i64) -> String {
+    match words {
+        0 => String::new(),
+        words if words > 0 => format!("+{words}"),
+        words => words.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::{DocumentBuilder, Paragraphs};
+    use crate::filter::TextFilter;
+
+    fn file(text: &str) -> LoadedFile {
+        let mut builder = DocumentBuilder::new();
+        builder.apply(TextFilter::new().lex(text));
+        LoadedFile {
+            path: PathBuf::from("ch1.md"),
+            document: Some(builder.finalize()),
+            from_pattern: false,
+            rank: 0,
+        }
+    }
+
+    fn row(position: &[(&str, usize)], own: u32, subtree: u32) -> RowData {
+        RowData {
+            path: PathBuf::from("ch1.md"),
+            heading: position.last().unwrap().0.to_string(),
+            level: 1,
+            location: Default::default(),
+            paragraphs: Paragraphs {
+                total: own,
+                ..Default::default()
+            },
+            parent: None,
+            position: position
+                .iter()
+                .map(|&(heading, index)| (heading.to_string(), index))
+                .collect(),
+            subtree_paragraphs: Paragraphs {
+                total: subtree,
+                ..Default::default()
+            },
+            has_children: false,
+            pinned_exception: false,
+        }
+    }
+
+    #[test]
+    fn changes_are_measured_from_the_start() {
+        let start = vec![file("# One\n\na b c\n\n## Two\n\nd e\n")];
+        let session = Session::start(&start);
+
+        let now = vec![file("# One\n\na b c d\n\n## Two\n\nd\n\n# Three\n\nf g\n")];
+        assert_eq!(session.words(&now), 2);
+        let one = [("One", 0)];
+        assert_eq!(session.row_words(&row(&one, 4, 5), false), 1);
+        assert_eq!(session.row_words(&row(&one, 4, 5), true), 0);
+        assert_eq!(
+            session.row_words(&row(&[("One", 0), ("Two", 0)], 1, 1), false),
+            -1
+        );
+        assert_eq!(session.row_words(&row(&[("Three", 0)], 2, 2), false), 2);
+    }
+
+    #[test]
+    fn repeated_headings_are_measured_apart() {
+        let session = Session::start(&[file(
+            "# Break
+
+a
+
+# Break
+
+b c d
+",
+        )]);
+        assert_eq!(session.row_words(&row(&[("Break", 0)], 1, 1), false), 0);
+        assert_eq!(session.row_words(&row(&[("Break", 1)], 3, 3), false), 0);
+        assert_eq!(session.row_words(&row(&[("Break", 1)], 4, 4), false), 1);
+    }
+
+    #[test]
+    fn words_per_minute_counts_at_least_a_minute() {
+        assert_eq!(words_per_minute(300, Duration::from_secs(10)), 300);
+        assert_eq!(words_per_minute(300, Duration::from_secs(600)), 30);
+        assert_eq!(words_per_minute(-50, Duration::from_secs(600)), 0);
+        assert_eq!(delta_label(12), "+12");
+        assert_eq!(delta_label(-3), "-3");
+        assert_eq!(delta_label(0), "");
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::Duration;
@@ -22,25 +22,32 @@ impl Watch {
     /// `paths` are the tracked files; their parent directories are watched.
     /// `extra_dirs` are additional directories to watch (the literal
     /// prefixes of live glob patterns — needed so a pattern that matches
-    /// nothing yet still sees its first match arrive).
+    /// nothing yet still sees its first match arrive), each flagged when
+    /// it should be watched recursively (patterns that reach below it).
     pub fn new<'a>(
         paths: impl Iterator<Item = &'a Path>,
-        extra_dirs: impl Iterator<Item = &'a Path>,
+        extra_dirs: impl Iterator<Item = (&'a Path, bool)>,
     ) -> Result<Self> {
         let tracked: HashSet<PathBuf> = paths.map(Path::to_path_buf).collect();
 
         let (tx, rx) = mpsc::channel();
         let mut debouncer = new_debouncer(DEBOUNCE, tx)?;
 
-        let mut dirs: HashSet<PathBuf> = tracked
+        let mut dirs: HashMap<PathBuf, RecursiveMode> = tracked
             .iter()
-            .filter_map(|p| p.parent().map(Path::to_path_buf))
+            .filter_map(|p| p.parent())
+            .map(|dir| (dir.to_path_buf(), RecursiveMode::NonRecursive))
             .collect();
-        dirs.extend(extra_dirs.map(Path::to_path_buf));
-        for dir in dirs {
-            debouncer
-                .watcher()
-                .watch(&dir, RecursiveMode::NonRecursive)?;
+        for (dir, recursive) in extra_dirs {
+            if recursive {
+                dirs.insert(dir.to_path_buf(), RecursiveMode::Recursive);
+            } else {
+                dirs.entry(dir.to_path_buf())
+                    .or_insert(RecursiveMode::NonRecursive);
+            }
+        }
+        for (dir, mode) in dirs {
+            debouncer.watcher().watch(&dir, mode)?;
         }
 
         Ok(Self {